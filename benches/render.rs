@@ -0,0 +1,121 @@
+//! Times resizing with each filter, rendering with each style and Floyd-Steinberg dithering
+//! of a synthetic image at 400 and 1600 columns, reporting throughput in pixels per second.
+//! Run with `cargo bench`, optionally followed by `-- FILTER` to only run matching benchmarks.
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use image::{DynamicImage, Rgb, Rgb32FImage, RgbImage};
+use std::hint::black_box;
+use ttview::dithering::Dither;
+use ttview::styling::{ASCII_GRADIENT, RenderOptions};
+use ttview::{DEFAULT_CELL_ASPECT, Filter, Style, resizing};
+
+/// Columns of output the benchmarks render at.
+const COLUMNS: [u32; 2] = [400, 1600];
+
+/// Photo sized image with smooth gradients and hard edges, the same on every run.
+fn source() -> DynamicImage {
+    let image = RgbImage::from_fn(1920, 1080, |x, y| {
+        let ring = ((x as f32 - 960.0).hypot(y as f32 - 540.0) / 40.0) as u32 % 2;
+        Rgb([
+            (x * 255 / 1919) as u8,
+            (y * 255 / 1079) as u8,
+            if ring == 0 { 40 } else { 220 },
+        ])
+    });
+    DynamicImage::ImageRgb8(image)
+}
+
+fn styles() -> Vec<Style> {
+    vec![
+        Style::Color,
+        Style::Greyscale,
+        Style::Gradient(ASCII_GRADIENT.chars().collect()),
+        Style::Braille,
+        Style::DitheredBraille,
+        Style::Dithered,
+        Style::Subpixel,
+        Style::BrailleEdges,
+        Style::BayerColor,
+        Style::ColorBraille,
+        Style::Quadrant,
+        Style::Sextant,
+        Style::Mono,
+        Style::Compact,
+    ]
+}
+
+const FILTERS: [(&str, Filter); 6] = [
+    ("nearest", Filter::Nearest),
+    ("triangle", Filter::Triangle),
+    ("catmull-rom", Filter::CatmullRom),
+    ("gaussian", Filter::Gaussian),
+    ("lanczos3", Filter::Lanczos3),
+    ("mitchell", Filter::Mitchell),
+];
+
+fn resize(c: &mut Criterion) {
+    let source = source();
+    let mut group = c.benchmark_group("resize");
+    // Resizing is measured by the pixels it reads.
+    group.throughput(Throughput::Elements(
+        source.width() as u64 * source.height() as u64,
+    ));
+    for columns in COLUMNS {
+        for (name, filter) in FILTERS {
+            group.bench_function(BenchmarkId::new(name, columns), |b| {
+                let dim = (Some(columns), None);
+                b.iter(|| black_box(resizing::resize(source.clone(), dim, filter, 1.0)));
+            });
+        }
+    }
+    group.finish();
+}
+
+fn apply(c: &mut Criterion) {
+    let source = source();
+    let options = RenderOptions::default();
+    let mut group = c.benchmark_group("apply");
+    for columns in COLUMNS {
+        for style in styles() {
+            let pixel_aspect = style.pixel_aspect(DEFAULT_CELL_ASPECT);
+            let (cell_width, _) = style.cell_size();
+            let dim = (Some(columns * cell_width), None);
+            let resized = resizing::resize(source.clone(), dim, Filter::Nearest, pixel_aspect);
+            let image = resized.to_rgb32f();
+            group.throughput(Throughput::Elements(
+                image.width() as u64 * image.height() as u64,
+            ));
+            group.bench_function(BenchmarkId::new(style.to_string(), columns), |b| {
+                b.iter(|| {
+                    let mut image = image.clone();
+                    black_box(style.apply_to_string(&mut image, &options))
+                });
+            });
+        }
+    }
+    group.finish();
+}
+
+fn floyd_steinberg(c: &mut Criterion) {
+    let source = source();
+    let mut group = c.benchmark_group("floyd-steinberg");
+    for columns in COLUMNS {
+        let dim = (Some(columns), None);
+        let resized = resizing::resize(source.clone(), dim, Filter::Nearest, 1.0);
+        let image: Rgb32FImage = resized.to_rgb32f();
+        group.throughput(Throughput::Elements(
+            image.width() as u64 * image.height() as u64,
+        ));
+        group.bench_function(BenchmarkId::from_parameter(columns), |b| {
+            b.iter(|| {
+                let mut image = image.clone();
+                Dither::FloydSteinberg.apply(&mut image, false, 0);
+                black_box(image)
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, resize, apply, floyd_steinberg);
+criterion_main!(benches);
@@ -0,0 +1,190 @@
+use image::{DynamicImage, Rgb32FImage, Rgba, Rgba32FImage};
+use std::str::FromStr;
+
+/// Brightness range mapped to the full display range.
+#[derive(Debug, Copy, Clone)]
+pub struct Window {
+    min: f32,
+    max: f32,
+}
+
+impl FromStr for Window {
+    type Err = String;
+
+    /// Parse a window from `MIN,MAX`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("invalid window '{s}', expected MIN,MAX with MIN < MAX");
+        let (min, max) = s.split_once(',').ok_or_else(error)?;
+        let min: f32 = min.trim().parse().map_err(|_| error())?;
+        let max: f32 = max.trim().parse().map_err(|_| error())?;
+        if min >= max {
+            return Err(error());
+        }
+        Ok(Self { min, max })
+    }
+}
+
+/// Stretch values inside the window linearly to [0, 1], clamping values outside of it.
+pub fn window(image: &mut Rgb32FImage, window: Window) {
+    for pixel in image.pixels_mut() {
+        for c in pixel.0.iter_mut() {
+            *c = ((*c - window.min) / (window.max - window.min)).clamp(0.0, 1.0);
+        }
+    }
+}
+
+/// Invert every channel of the image.
+pub fn invert(image: &mut Rgb32FImage) {
+    for pixel in image.pixels_mut() {
+        for c in pixel.0.iter_mut() {
+            *c = 1.0 - *c;
+        }
+    }
+}
+
+/// Scale all values so that the largest one becomes 1, leaving black images untouched.
+pub fn normalize(image: &mut Rgb32FImage) {
+    let max = image.pixels().flat_map(|pixel| pixel.0).fold(0.0, f32::max);
+    if max > 0.0 {
+        for pixel in image.pixels_mut() {
+            for c in pixel.0.iter_mut() {
+                *c /= max;
+            }
+        }
+    }
+}
+
+/// Set pixels brighter than `threshold` to white and all others to black.
+pub fn binarize(image: &mut Rgb32FImage, threshold: f32) {
+    for pixel in image.pixels_mut() {
+        let value = if pixel.0.iter().sum::<f32>() / 3.0 > threshold {
+            1.0
+        } else {
+            0.0
+        };
+        pixel.0 = [value; 3];
+    }
+}
+
+/// Scale all values by `2^stops`, like changing the exposure of a camera.
+pub fn exposure(image: &mut Rgb32FImage, stops: f32) {
+    let factor = stops.exp2();
+    for pixel in image.pixels_mut() {
+        for c in pixel.0.iter_mut() {
+            *c *= factor;
+        }
+    }
+}
+
+/// Add `offset` to every channel, clamping to [0, 1].
+pub fn brightness(image: &mut Rgb32FImage, offset: f32) {
+    for pixel in image.pixels_mut() {
+        for c in pixel.0.iter_mut() {
+            *c = (*c + offset).clamp(0.0, 1.0);
+        }
+    }
+}
+
+/// Scale the distance of every channel from middle grey by `factor`, clamping to [0, 1].
+pub fn contrast(image: &mut Rgb32FImage, factor: f32) {
+    for pixel in image.pixels_mut() {
+        for c in pixel.0.iter_mut() {
+            *c = ((*c - 0.5) * factor + 0.5).clamp(0.0, 1.0);
+        }
+    }
+}
+
+/// Interpolate every pixel between its grey value at 0 and itself at 1 by `factor`,
+/// extrapolating beyond 1 and clamping to [0, 1].
+pub fn saturation(image: &mut Rgb32FImage, factor: f32) {
+    for pixel in image.pixels_mut() {
+        let [r, g, b] = pixel.0;
+        let grey = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        for c in pixel.0.iter_mut() {
+            *c = (grey + (*c - grey) * factor).clamp(0.0, 1.0);
+        }
+    }
+}
+
+/// Raise every channel to the power of `1 / gamma`, brightening midtones for a gamma above 1
+/// and darkening them below 1. Black and white stay as they are.
+pub fn gamma(image: &mut Rgb32FImage, gamma: f32) {
+    let exponent = gamma.recip();
+    for pixel in image.pixels_mut() {
+        for c in pixel.0.iter_mut() {
+            *c = c.max(0.0).powf(exponent);
+        }
+    }
+}
+
+/// Compress values above 1 of HDR images into the displayable range with the extended
+/// Reinhard operator on luminance, keeping hues. The brightest pixel becomes white and
+/// images without values above 1 are left as they are.
+pub fn tonemap(image: &mut Rgb32FImage) {
+    let luminance = |[r, g, b]: [f32; 3]| 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let white = image
+        .pixels()
+        .map(|pixel| luminance(pixel.0))
+        .fold(1.0, f32::max);
+    for pixel in image.pixels_mut() {
+        let before = luminance(pixel.0);
+        if before <= 0.0 {
+            continue;
+        }
+        let after = before * (1.0 + before / (white * white)) / (1.0 + before);
+        for c in pixel.0.iter_mut() {
+            *c = (*c * after / before).clamp(0.0, 1.0);
+        }
+    }
+}
+
+/// Blend each pixel slightly toward the mean of its four neighbors,
+/// softening staircases on diagonal edges while leaving flat regions untouched.
+pub fn smooth(image: &mut Rgb32FImage) {
+    const STRENGTH: f32 = 0.25;
+    let source = image.clone();
+    let (width, height) = source.dimensions();
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let neighbors = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        let mut sum = [0.0; 3];
+        let mut count = 0.0;
+        for (nx, ny) in neighbors {
+            if nx < width && ny < height {
+                let neighbor = source.get_pixel(nx, ny);
+                (0..3).for_each(|c| sum[c] += neighbor[c]);
+                count += 1.0;
+            }
+        }
+        for c in 0..3 {
+            pixel[c] += (sum[c] / count - pixel[c]) * STRENGTH;
+        }
+    }
+}
+
+/// Remove salt-and-pepper noise with a 3x3 median filter on each channel.
+/// Neighbors outside the image are skipped.
+pub fn despeckle(image: &DynamicImage) -> DynamicImage {
+    let source = image.to_rgba32f();
+    let (width, height) = source.dimensions();
+    let filtered = Rgba32FImage::from_fn(width, height, |x, y| {
+        let mut neighborhood = Vec::with_capacity(9);
+        for ny in y.saturating_sub(1)..=(y + 1).min(height - 1) {
+            for nx in x.saturating_sub(1)..=(x + 1).min(width - 1) {
+                neighborhood.push(*source.get_pixel(nx, ny));
+            }
+        }
+        let mut median = [0.0; 4];
+        for (c, value) in median.iter_mut().enumerate() {
+            let mut channel: Vec<f32> = neighborhood.iter().map(|pixel| pixel[c]).collect();
+            channel.sort_unstable_by(f32::total_cmp);
+            *value = channel[channel.len() / 2];
+        }
+        Rgba(median)
+    });
+    DynamicImage::ImageRgba32F(filtered)
+}
@@ -0,0 +1,174 @@
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::{AnimationDecoder, DynamicImage, ImageError, ImageFormat, ImageResult};
+use std::fs::File;
+use std::io::{BufReader, IsTerminal, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Delay for frames that do not specify one, as used by browsers.
+const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+
+/// Set while frames are being played, so Ctrl-C stops playback instead of the process.
+static PLAYING: AtomicBool = AtomicBool::new(false);
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Decode every frame of an animated GIF, APNG or WebP image along with how long it is shown.
+/// Returns `None` for still images and formats without animation.
+pub fn frames(
+    path: &Path,
+    format: Option<ImageFormat>,
+) -> ImageResult<Option<Vec<(DynamicImage, Duration)>>> {
+    let reader = || {
+        File::open(path)
+            .map(BufReader::new)
+            .map_err(ImageError::IoError)
+    };
+    let frames = match format {
+        Some(ImageFormat::Gif) => GifDecoder::new(reader()?)?.into_frames(),
+        Some(ImageFormat::Png) => {
+            let decoder = PngDecoder::new(reader()?)?;
+            if !decoder.is_apng()? {
+                return Ok(None);
+            }
+            decoder.apng()?.into_frames()
+        }
+        Some(ImageFormat::WebP) => {
+            let decoder = WebPDecoder::new(reader()?)?;
+            if !decoder.has_animation() {
+                return Ok(None);
+            }
+            decoder.into_frames()
+        }
+        _ => return Ok(None),
+    };
+    let frames = frames
+        .map(|frame| {
+            let frame = frame?;
+            let delay = Duration::from(frame.delay());
+            let delay = if delay.is_zero() {
+                DEFAULT_DELAY
+            } else {
+                delay
+            };
+            Ok((DynamicImage::ImageRgba8(frame.into_buffer()), delay))
+        })
+        .collect::<ImageResult<Vec<_>>>()?;
+    Ok((frames.len() > 1).then_some(frames))
+}
+
+/// Whether playback was stopped with Ctrl-C.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// Index of the frame on screen at `elapsed` into a loop of frames shown for `delays`.
+fn frame_at(delays: &[Duration], elapsed: Duration) -> usize {
+    let mut end = Duration::ZERO;
+    delays
+        .iter()
+        .position(|delay| {
+            end += *delay;
+            elapsed < end
+        })
+        .unwrap_or(delays.len() - 1)
+}
+
+/// Show rendered frames in place from the top of the screen, `loops` times or forever
+/// when zero, and at most `fps` frames per second if given. Frames that are due while
+/// the terminal is still busy with earlier ones are skipped to keep up.
+/// Before each frame, `refit` can return all frames rendered again, e.g. for a new terminal
+/// size, which replace the old ones without restarting the animation.
+/// Output that is not a terminal is a capture to replay with `cat`, which gets every frame
+/// in turn without waiting, `loops` times or once when zero.
+/// Ctrl-C ends playback and restores the cursor.
+pub fn play(
+    mut frames: Vec<(String, Duration)>,
+    loops: u32,
+    fps: Option<u32>,
+    mut refit: impl FnMut() -> Option<Vec<String>>,
+) -> std::io::Result<()> {
+    // Only the first handler can be installed, which is the one we want anyway.
+    let _ = ctrlc::set_handler(|| {
+        if PLAYING.load(Ordering::Relaxed) {
+            INTERRUPTED.store(true, Ordering::Relaxed);
+        } else {
+            std::process::exit(130);
+        }
+    });
+    PLAYING.store(true, Ordering::Relaxed);
+    let mut stdout = std::io::stdout().lock();
+    let live = stdout.is_terminal();
+    let mut show = || -> std::io::Result<()> {
+        write!(stdout, "\x1B[?25l\x1B[H\x1B[2J")?;
+        if !live {
+            for _ in 0..loops.max(1) {
+                for (output, _) in &frames {
+                    write!(stdout, "\x1B[H{output}")?;
+                }
+            }
+            return stdout.flush();
+        }
+        let delays: Vec<Duration> = frames.iter().map(|(_, delay)| *delay).collect();
+        let length: Duration = delays.iter().sum();
+        let interval = fps.map_or(Duration::ZERO, |fps| Duration::from_secs(1) / fps);
+        // Frames follow the clock, so skipped frames do not slow down the animation.
+        let start = Instant::now();
+        loop {
+            let elapsed = start.elapsed();
+            let played = (elapsed.as_nanos() / length.as_nanos()) as u32;
+            if (loops != 0 && played >= loops) || interrupted() {
+                return Ok(());
+            }
+            if let Some(outputs) = refit() {
+                for ((output, _), refitted) in frames.iter_mut().zip(outputs) {
+                    *output = refitted;
+                }
+                // Smaller frames would leave parts of the old ones behind.
+                write!(stdout, "\x1B[2J")?;
+            }
+            let loop_start = length * played;
+            let index = frame_at(&delays, elapsed - loop_start);
+            let shown = Instant::now();
+            write!(stdout, "\x1B[H{}", frames[index].0)?;
+            stdout.flush()?;
+            // Wait for the next frame, or longer to stay below the frame rate.
+            let next = start + loop_start + delays[..=index].iter().sum::<Duration>();
+            let mut due = next.max(shown + interval);
+            if loops != 0 {
+                due = due.min(start + length * loops);
+            }
+            std::thread::sleep(due.saturating_duration_since(Instant::now()));
+        }
+    };
+    let result = show();
+    PLAYING.store(false, Ordering::Relaxed);
+    writeln!(std::io::stdout(), "\x1B[?25h")?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_at_follows_the_delays() {
+        let delays = [100, 50, 200].map(Duration::from_millis);
+        let at = |ms| frame_at(&delays, Duration::from_millis(ms));
+        assert_eq!(at(0), 0);
+        assert_eq!(at(99), 0);
+        assert_eq!(at(100), 1);
+        assert_eq!(at(149), 1);
+        assert_eq!(at(150), 2);
+        assert_eq!(at(349), 2);
+    }
+
+    #[test]
+    fn frame_at_the_end_stays_on_the_last_frame() {
+        let delays = [100, 100].map(Duration::from_millis);
+        assert_eq!(frame_at(&delays, Duration::from_millis(200)), 1);
+        assert_eq!(frame_at(&delays, Duration::from_secs(5)), 1);
+    }
+}
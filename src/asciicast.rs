@@ -0,0 +1,59 @@
+use std::io::Write;
+use ttview::json;
+
+/// Terminal recording in the asciicast v2 format used by asciinema.
+#[derive(Debug, Default)]
+pub struct Recording {
+    width: u32,
+    height: u32,
+    events: Vec<(f64, String)>,
+}
+
+impl Recording {
+    /// Record `output` at `time` seconds, growing the terminal to fit `size` as `(columns, rows)`.
+    pub fn push(&mut self, time: f64, output: &str, size: (u32, u32)) {
+        self.width = self.width.max(size.0);
+        self.height = self.height.max(size.1);
+        // Recordings capture raw terminal output, where line feeds do not return the cursor.
+        self.events.push((time, output.replace('\n', "\r\n")));
+    }
+
+    /// Write the header followed by one line per output event.
+    pub fn write(&self, mut writer: impl Write) -> std::io::Result<()> {
+        writeln!(
+            writer,
+            r#"{{"version": 2, "width": {}, "height": {}}}"#,
+            self.width, self.height
+        )?;
+        for (time, output) in &self.events {
+            writeln!(writer, r#"[{time:.6}, "o", {}]"#, json::string(output))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_and_events() {
+        let mut recording = Recording::default();
+        recording.push(0.0, "\x1B[H\x1B[2J", (4, 2));
+        recording.push(0.0, "\x1B[H▀▀\n▀▀\n", (4, 2));
+        recording.push(0.1, "\x1B[H\"a\"\n", (6, 1));
+        let mut output = Vec::new();
+        recording.write(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                r#"{"version": 2, "width": 6, "height": 2}"#,
+                r#"[0.000000, "o", "\u001b[H\u001b[2J"]"#,
+                r#"[0.000000, "o", "\u001b[H▀▀\r\n▀▀\r\n"]"#,
+                r#"[0.100000, "o", "\u001b[H\"a\"\r\n"]"#,
+            ]
+        );
+    }
+}
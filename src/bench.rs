@@ -0,0 +1,54 @@
+use crate::resizing::{Filter, resize};
+use crate::styling::Style;
+use crate::{Args, build_display_string, load_image, target_size};
+use clap::ValueEnum;
+use image::GenericImageView;
+use std::process::ExitCode;
+use std::time::Instant;
+
+/// Render the image with every style and filter, printing render times and output sizes.
+/// Fails if the image cannot be loaded.
+pub fn run(filename: &str, args: &Args) -> ExitCode {
+    let image = match load_image(filename, !args.no_auto_orient) {
+        Ok(loaded) => loaded.image,
+        Err(err) => {
+            eprintln!("{filename}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let dim = target_size(args, image.dimensions());
+    println!(
+        "{:<16} {:<12} {:>10} {:>10} {:>10}",
+        "style", "filter", "resize", "render", "bytes"
+    );
+    for filter in Filter::value_variants() {
+        let start = Instant::now();
+        let resized = resize(image.clone(), dim, *filter, args.pixel_aspect());
+        let resize_time = start.elapsed();
+        // Auto only picks one of the other styles.
+        let styles = Style::value_variants()
+            .iter()
+            .filter(|style| !matches!(style, Style::Auto));
+        for style in styles {
+            let args = Args {
+                style: style.clone(),
+                ..args.clone()
+            };
+            let start = Instant::now();
+            let output = build_display_string(&resized, &args);
+            let render_time = start.elapsed();
+            println!(
+                "{:<16} {:<12} {:>10} {:>10} {:>10}",
+                style.to_string(),
+                filter
+                    .to_possible_value()
+                    .expect("filters have names")
+                    .get_name(),
+                format!("{:.2?}", resize_time),
+                format!("{:.2?}", render_time),
+                output.len()
+            );
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,229 @@
+use crate::Pixel;
+use image::Rgb32FImage;
+use std::collections::HashMap;
+
+/// Channel levels of the 6x6x6 color cube in the xterm 256 color palette.
+pub const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Colors the terminal can display.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum, serde::Deserialize))]
+pub enum ColorDepth {
+    /// The xterm 256 color palette.
+    #[cfg_attr(feature = "cli", value(name = "256"), serde(rename = "256"))]
+    Ansi256,
+
+    /// 24 bit true color.
+    #[default]
+    #[cfg_attr(feature = "cli", value(name = "24bit"), serde(rename = "24bit"))]
+    TrueColor,
+}
+
+/// Weights of the red, green and blue channels in the brightness of a pixel.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Luma {
+    /// ITU-R BT.601 weights of older video and JPEG.
+    Rec601,
+
+    /// ITU-R BT.709 weights of sRGB and modern content.
+    #[default]
+    Rec709,
+
+    /// All channels weighted equally, so that no hue looks brighter than another.
+    Average,
+}
+
+impl Luma {
+    /// Weights of the red, green and blue channels, adding up to 1.
+    pub fn weights(&self) -> [f32; 3] {
+        match self {
+            Self::Rec601 => [0.299, 0.587, 0.114],
+            Self::Rec709 => [0.2126, 0.7152, 0.0722],
+            Self::Average => [1.0 / 3.0; 3],
+        }
+    }
+}
+
+/// Index of the closest color in the xterm 256 color palette,
+/// chosen from the 6x6x6 color cube and the greyscale ramp.
+pub fn xterm_index(color: [u8; 3]) -> u8 {
+    let distance = |other: [u8; 3]| -> i32 {
+        (0..3)
+            .map(|c| (color[c] as i32 - other[c] as i32).pow(2))
+            .sum()
+    };
+    let level = |c: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (c as i32 - CUBE_LEVELS[i] as i32).abs())
+            .unwrap()
+    };
+    let [r, g, b] = color.map(level);
+    let cube = [r, g, b].map(|i| CUBE_LEVELS[i]);
+    // The ramp runs from 8 to 238 in steps of 10, skipping black and white.
+    let average = color.iter().map(|&c| c as i32).sum::<i32>() / 3;
+    let step = ((average - 3) / 10).clamp(0, 23);
+    let grey = (8 + 10 * step) as u8;
+    if distance([grey; 3]) < distance(cube) {
+        232 + step as u8
+    } else {
+        (16 + 36 * r + 6 * g + b) as u8
+    }
+}
+
+/// Parse a color in `RRGGBB` hex notation, optionally prefixed with `#`.
+pub fn parse_hex(s: &str) -> Result<Pixel, String> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(format!("invalid color '{s}', expected RRGGBB"));
+    }
+    let mut channels = [0.0; 3];
+    for (i, channel) in channels.iter_mut().enumerate() {
+        let value = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16)
+            .map_err(|_| format!("invalid color '{s}', expected RRGGBB"))?;
+        *channel = value as f32 / 255.0;
+    }
+    Ok(Pixel::from(channels))
+}
+
+/// Parse at least two `RRGGBB` colors separated by `-`, as in `0000ff-ffffff-ff0000`.
+pub fn parse_hex_list(s: &str) -> Result<Vec<Pixel>, String> {
+    let colors = s.split('-').map(parse_hex).collect::<Result<Vec<_>, _>>()?;
+    if colors.len() < 2 {
+        return Err(format!(
+            "invalid color gradient '{s}', expected at least two colors"
+        ));
+    }
+    Ok(colors)
+}
+
+/// Convert an sRGB encoded channel value to linear light.
+pub fn to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Convert a linear light channel value to sRGB encoding.
+pub fn to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Convert a channel value to 8 bits, rounding to the nearest level and clamping values
+/// outside of `0.0..=1.0`, which adjustments and filters with negative lobes can produce.
+pub fn to_u8(c: f32) -> u8 {
+    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Pixels of one distinct color, or of a group of merged ones.
+#[derive(Debug, Copy, Clone)]
+struct Cluster {
+    sum: [f32; 3],
+    count: f32,
+}
+
+impl Cluster {
+    fn mean(&self) -> [f32; 3] {
+        self.sum.map(|c| c / self.count)
+    }
+}
+
+/// Reduce the number of distinct colors in each line of `line_height` pixel rows to `max_colors`
+/// with median cut: the group of colors spanning the widest range in a channel is split in two
+/// halves of about as many pixels, until there are enough groups. Each pixel then takes the
+/// average color of its group.
+pub fn limit_colors_per_line(image: &mut Rgb32FImage, line_height: u32, max_colors: usize) {
+    for top in (0..image.height()).step_by(line_height as usize) {
+        let rows = top..(top + line_height).min(image.height());
+        // Distinct 8 bit colors of this line and the pixels of each.
+        let mut distinct: HashMap<[u8; 3], Cluster> = HashMap::new();
+        for y in rows.clone() {
+            for x in 0..image.width() {
+                let color = image.get_pixel(x, y).0;
+                let cluster = distinct.entry(color.map(to_u8)).or_insert(Cluster {
+                    sum: [0.0; 3],
+                    count: 0.0,
+                });
+                (0..3).for_each(|i| cluster.sum[i] += color[i]);
+                cluster.count += 1.0;
+            }
+        }
+        if distinct.len() <= max_colors {
+            continue;
+        }
+        // Sorted so that lines are split the same way on every run.
+        let mut colors: Vec<([u8; 3], Cluster)> = distinct.into_iter().collect();
+        colors.sort_unstable_by_key(|(key, _)| *key);
+        // Ranges of `colors` merged into one.
+        let mut groups = Vec::with_capacity(max_colors);
+        groups.push(0..colors.len());
+        while groups.len() < max_colors {
+            let widest = groups
+                .iter()
+                .enumerate()
+                .filter(|(_, group)| group.len() > 1)
+                .map(|(i, group)| {
+                    let (channel, range) = widest_channel(&colors[group.clone()]);
+                    (range, i, channel)
+                })
+                .max_by_key(|&(range, i, _)| (range, std::cmp::Reverse(i)));
+            let Some((_, index, channel)) = widest else {
+                break;
+            };
+            let group = groups[index].clone();
+            let members = &mut colors[group.clone()];
+            members.sort_unstable_by_key(|(key, _)| (key[channel], *key));
+            // Split where half of the pixels are on either side, leaving neither half empty.
+            let total: f32 = members.iter().map(|(_, cluster)| cluster.count).sum();
+            let mut below = 0.0;
+            let median = members
+                .iter()
+                .position(|(_, cluster)| {
+                    below += cluster.count;
+                    below >= total / 2.0
+                })
+                .map_or(1, |i| i + 1)
+                .clamp(1, members.len() - 1);
+            groups[index] = group.start..group.start + median;
+            groups.push(group.start + median..group.end);
+        }
+        let mut merged = HashMap::with_capacity(colors.len());
+        for group in groups {
+            let members = &colors[group];
+            let mut total = Cluster {
+                sum: [0.0; 3],
+                count: 0.0,
+            };
+            for (_, cluster) in members {
+                (0..3).for_each(|i| total.sum[i] += cluster.sum[i]);
+                total.count += cluster.count;
+            }
+            let mean = Pixel::from(total.mean());
+            merged.extend(members.iter().map(|(key, _)| (*key, mean)));
+        }
+        for y in rows {
+            for x in 0..image.width() {
+                let pixel = image.get_pixel_mut(x, y);
+                *pixel = merged[&pixel.0.map(to_u8)];
+            }
+        }
+    }
+}
+
+/// Channel in which colors differ the most, along with the difference.
+fn widest_channel(colors: &[([u8; 3], Cluster)]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let values = colors.iter().map(|(key, _)| key[channel]);
+            let range = values.clone().max().unwrap_or(0) - values.min().unwrap_or(0);
+            (channel, range)
+        })
+        .max_by_key(|&(channel, range)| (range, std::cmp::Reverse(channel)))
+        .unwrap()
+}
@@ -0,0 +1,85 @@
+use crate::Pixel;
+use crate::colors::{parse_hex, to_linear, to_srgb};
+use image::{Rgb32FImage, Rgba32FImage};
+use std::str::FromStr;
+
+/// Background to composite transparent images onto.
+#[derive(Debug, Clone)]
+pub enum Background {
+    /// Checkerboard of `width` x `height` pixel tiles alternating between two colors.
+    Tiles {
+        width: u32,
+        height: u32,
+        colors: [Pixel; 2],
+    },
+
+    /// Single color.
+    Solid(Pixel),
+}
+
+impl Background {
+    /// Background color at the given pixel.
+    fn color_at(&self, x: u32, y: u32) -> Pixel {
+        match self {
+            Self::Tiles {
+                width,
+                height,
+                colors,
+            } => colors[((x / width + y / height) % 2) as usize],
+            Self::Solid(color) => *color,
+        }
+    }
+}
+
+impl FromStr for Background {
+    type Err = String;
+
+    /// Parse a tile background from `W,H,RRGGBB,RRGGBB`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(',').collect();
+        let [width, height, first, second] = parts[..] else {
+            return Err(format!(
+                "invalid tile background '{s}', expected W,H,RRGGBB,RRGGBB"
+            ));
+        };
+        let parse_size = |value: &str| match value.trim().parse::<u32>() {
+            Ok(0) | Err(_) => Err(format!("invalid tile size '{value}'")),
+            Ok(size) => Ok(size),
+        };
+        Ok(Self::Tiles {
+            width: parse_size(width)?,
+            height: parse_size(height)?,
+            colors: [parse_hex(first.trim())?, parse_hex(second.trim())?],
+        })
+    }
+}
+
+/// Blend an image with transparency onto a background, dropping the alpha channel.
+/// Blending happens in linear light so that anti-aliased edges keep their brightness.
+pub fn composite(image: &Rgba32FImage, background: &Background) -> Rgb32FImage {
+    Rgb32FImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        // Opaque pixels skip the round trip through linear light, which is not exact.
+        if a >= 1.0 {
+            return Pixel::from([r, g, b]);
+        }
+        let back = background.color_at(x, y).0;
+        let blend =
+            |front: f32, back: f32| to_srgb(to_linear(front) * a + to_linear(back) * (1.0 - a));
+        Pixel::from([blend(r, back[0]), blend(g, back[1]), blend(b, back[2])])
+    })
+}
+
+/// Make every pixel further than `tolerance` from `key` fully transparent.
+/// The tolerance is a distance in 8 bit RGB space.
+pub fn mask(image: &mut Rgba32FImage, key: Pixel, tolerance: f32) {
+    for pixel in image.pixels_mut() {
+        let distance = (0..3)
+            .map(|c| ((pixel[c] - key[c]) * 255.0).powi(2))
+            .sum::<f32>()
+            .sqrt();
+        if distance > tolerance {
+            pixel[3] = 0.0;
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::Args;
+use crate::colors::ColorDepth;
+use crate::compositing::Background;
+use crate::resizing::Filter;
+use crate::styling::Style;
+use crate::terminal::Theme;
+use clap::ArgMatches;
+use clap::parser::ValueSource;
+use std::path::{Path, PathBuf};
+
+/// Defaults read from the config file, overridden by command line arguments.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    style: Option<Style>,
+    gradient: Option<String>,
+    filter: Option<Filter>,
+    width: Option<u32>,
+    height: Option<u32>,
+    tile_background: Option<String>,
+    max_colors_per_line: Option<u32>,
+    auto_contrast: Option<bool>,
+    theme: Option<Theme>,
+    colors: Option<ColorDepth>,
+}
+
+/// Location of the config file, `$XDG_CONFIG_HOME/ttview/config.toml` or `~/.config/ttview/config.toml`.
+pub fn default_path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config_home.join("ttview").join("config.toml"))
+}
+
+impl Config {
+    /// Read the config file at `path`, returning `None` if it does not exist.
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.to_string()),
+        };
+        toml::from_str(&text)
+            .map(Some)
+            .map_err(|err| err.to_string())
+    }
+
+    /// Fill in all arguments not given on the command line with values from the config.
+    pub fn apply(self, args: &mut Args, matches: &ArgMatches) -> Result<(), String> {
+        let explicit = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        let explicit_style = ["style", "gradient", "gradient_preset", "color_gradient"];
+        if !explicit_style.iter().any(|id| explicit(id)) {
+            if let Some(gradient) = self.gradient {
+                args.gradient = Some(crate::parse_gradient(&gradient)?);
+            } else if let Some(style) = self.style {
+                args.style = style;
+            }
+        }
+        // Width and height only make sense together, a single explicit one replaces both.
+        if !explicit("width") && !explicit("height") {
+            if self.width == Some(0) || self.height == Some(0) {
+                return Err("width and height must be at least 1".to_string());
+            }
+            args.width = self.width.or(args.width);
+            args.height = self.height.or(args.height);
+        }
+        if !explicit("filter") {
+            args.filter = self.filter.or(args.filter);
+        }
+        if !explicit("tile_background")
+            && let Some(background) = self.tile_background
+        {
+            args.tile_background = Some(background.parse::<Background>()?);
+        }
+        if !explicit("max_colors_per_line") {
+            args.max_colors_per_line = self.max_colors_per_line.or(args.max_colors_per_line);
+        }
+        if !explicit("auto_contrast") {
+            args.auto_contrast = self.auto_contrast.unwrap_or(args.auto_contrast);
+        }
+        if !explicit("theme") {
+            args.theme = self.theme.or(args.theme);
+        }
+        if !explicit("colors")
+            && let Some(colors) = self.colors
+        {
+            args.colors = colors;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, FromArgMatches};
+
+    /// Arguments parsed from a command line, along with where each value came from.
+    fn parse(command_line: &[&str]) -> (Args, ArgMatches) {
+        let matches = Args::command().try_get_matches_from(command_line).unwrap();
+        (Args::from_arg_matches(&matches).unwrap(), matches)
+    }
+
+    #[test]
+    fn colors_are_parsed() {
+        let config: Config = toml::from_str("colors = \"256\"").unwrap();
+        assert_eq!(config.colors, Some(ColorDepth::Ansi256));
+        let config: Config = toml::from_str("colors = \"24bit\"").unwrap();
+        assert_eq!(config.colors, Some(ColorDepth::TrueColor));
+        assert!(toml::from_str::<Config>("colors = \"16\"").is_err());
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let err = toml::from_str::<Config>("colours = \"256\"").unwrap_err();
+        assert!(err.to_string().contains("colours"), "{err}");
+    }
+
+    #[test]
+    fn command_line_overrides_the_config() {
+        let config = || {
+            toml::from_str::<Config>("style = \"braille\"\nwidth = 10\nmax-colors-per-line = 8")
+                .unwrap()
+        };
+        let (mut args, matches) = parse(&["ttview", "image.png"]);
+        config().apply(&mut args, &matches).unwrap();
+        assert!(matches!(args.style, Style::Braille));
+        assert_eq!(args.width, Some(10));
+        assert_eq!(args.max_colors_per_line, Some(8));
+
+        let (mut args, matches) = parse(&[
+            "ttview",
+            "--style",
+            "color",
+            "--height",
+            "20",
+            "--max-colors-per-line",
+            "4",
+            "image.png",
+        ]);
+        config().apply(&mut args, &matches).unwrap();
+        assert!(matches!(args.style, Style::Color));
+        // An explicit height replaces the configured width as well.
+        assert_eq!((args.width, args.height), (None, Some(20)));
+        assert_eq!(args.max_colors_per_line, Some(4));
+    }
+}
@@ -0,0 +1,369 @@
+use crate::colors::CUBE_LEVELS;
+use image::Rgb32FImage;
+use std::sync::{Arc, Mutex};
+
+/// Algorithm reducing images to black and white for the dithered styles.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Dither {
+    /// Floyd-Steinberg error diffusion.
+    #[default]
+    FloydSteinberg,
+    /// Atkinson error diffusion, which drops a quarter of the error for more contrast.
+    Atkinson,
+    /// Jarvis-Judice-Ninke error diffusion over two rows.
+    JarvisJudiceNinke,
+    /// Sierra error diffusion over two rows.
+    Sierra,
+    /// Stucki error diffusion over two rows.
+    Stucki,
+    /// Ordered dithering with a 2x2 Bayer matrix.
+    Bayer2,
+    /// Ordered dithering with a 4x4 Bayer matrix.
+    Bayer4,
+    /// Ordered dithering with an 8x8 Bayer matrix.
+    Bayer8,
+    /// Thresholds from a tiled blue noise texture, which has no visible pattern or
+    /// direction. The texture is generated from a seed.
+    #[cfg_attr(feature = "cli", value(alias = "bluenoise"))]
+    BlueNoise,
+}
+
+/// Error diffusion kernel as `(dx, dy, weight)` offsets from the current pixel,
+/// with weights relative to the divisor.
+struct Kernel {
+    weights: &'static [(i32, u32, f32)],
+    divisor: f32,
+}
+
+const FLOYD_STEINBERG: Kernel = Kernel {
+    weights: &[(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)],
+    divisor: 16.0,
+};
+
+const ATKINSON: Kernel = Kernel {
+    weights: &[
+        (1, 0, 1.0),
+        (2, 0, 1.0),
+        (-1, 1, 1.0),
+        (0, 1, 1.0),
+        (1, 1, 1.0),
+        (0, 2, 1.0),
+    ],
+    divisor: 8.0,
+};
+
+const JARVIS_JUDICE_NINKE: Kernel = Kernel {
+    weights: &[
+        (1, 0, 7.0),
+        (2, 0, 5.0),
+        (-2, 1, 3.0),
+        (-1, 1, 5.0),
+        (0, 1, 7.0),
+        (1, 1, 5.0),
+        (2, 1, 3.0),
+        (-2, 2, 1.0),
+        (-1, 2, 3.0),
+        (0, 2, 5.0),
+        (1, 2, 3.0),
+        (2, 2, 1.0),
+    ],
+    divisor: 48.0,
+};
+
+const SIERRA: Kernel = Kernel {
+    weights: &[
+        (1, 0, 5.0),
+        (2, 0, 3.0),
+        (-2, 1, 2.0),
+        (-1, 1, 4.0),
+        (0, 1, 5.0),
+        (1, 1, 4.0),
+        (2, 1, 2.0),
+        (-1, 2, 2.0),
+        (0, 2, 3.0),
+        (1, 2, 2.0),
+    ],
+    divisor: 32.0,
+};
+
+const STUCKI: Kernel = Kernel {
+    weights: &[
+        (1, 0, 8.0),
+        (2, 0, 4.0),
+        (-2, 1, 2.0),
+        (-1, 1, 4.0),
+        (0, 1, 8.0),
+        (1, 1, 4.0),
+        (2, 1, 2.0),
+        (-2, 2, 1.0),
+        (-1, 2, 2.0),
+        (0, 2, 4.0),
+        (1, 2, 2.0),
+        (2, 2, 1.0),
+    ],
+    divisor: 42.0,
+};
+
+impl Dither {
+    /// Set every channel of the image to either 0 or 1. With `serpentine`, error diffusion
+    /// scans every other row right to left, avoiding the diagonal worms of one direction.
+    /// Blue noise uses the texture generated from `seed`, the same one for the same seed.
+    pub fn apply(&self, image: &mut Rgb32FImage, serpentine: bool, seed: u64) {
+        match self {
+            Self::FloydSteinberg => diffuse(image, &FLOYD_STEINBERG, serpentine),
+            Self::Atkinson => diffuse(image, &ATKINSON, serpentine),
+            Self::JarvisJudiceNinke => diffuse(image, &JARVIS_JUDICE_NINKE, serpentine),
+            Self::Sierra => diffuse(image, &SIERRA, serpentine),
+            Self::Stucki => diffuse(image, &STUCKI, serpentine),
+            Self::Bayer2 => ordered(image, 2),
+            Self::Bayer4 => ordered(image, 4),
+            Self::Bayer8 => ordered(image, 8),
+            Self::BlueNoise => blue_noise(image, seed),
+        }
+    }
+
+    /// Whether errors spread to neighboring pixels, making each pixel depend on the ones
+    /// rendered before it instead of only its position.
+    pub fn diffuses(&self) -> bool {
+        !matches!(
+            self,
+            Self::Bayer2 | Self::Bayer4 | Self::Bayer8 | Self::BlueNoise
+        )
+    }
+}
+
+/// Round each channel to 0 or 1, spreading the rounding error to unvisited neighbors.
+/// Rows scanned right to left, every other one if `serpentine`, mirror the kernel.
+fn diffuse(image: &mut Rgb32FImage, kernel: &Kernel, serpentine: bool) {
+    let (width, height) = image.dimensions();
+    for y in 0..height {
+        let reverse = serpentine && y % 2 == 1;
+        let direction = if reverse { -1 } else { 1 };
+        for i in 0..width {
+            let x = if reverse { width - 1 - i } else { i };
+            let pixel = image.get_pixel_mut(x, y);
+            let mut error = [0.0; 3];
+            for (c, value) in pixel.0.iter_mut().enumerate() {
+                let quantized = if *value < 0.5 { 0.0 } else { 1.0 };
+                error[c] = *value - quantized;
+                *value = quantized;
+            }
+            for &(dx, dy, weight) in kernel.weights {
+                let nx = x as i32 + dx * direction;
+                if nx < 0 {
+                    continue;
+                }
+                if let Some(pixel) = image.get_pixel_mut_checked(nx as u32, y + dy) {
+                    for c in 0..3 {
+                        pixel[c] += error[c] * weight / kernel.divisor;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Round each channel to 0 or 1 by comparing it to a tiled `size` x `size` Bayer matrix.
+fn ordered(image: &mut Rgb32FImage, size: u32) {
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let threshold = bayer_threshold(size, x, y);
+        for value in pixel.0.iter_mut() {
+            *value = if *value > threshold { 1.0 } else { 0.0 };
+        }
+    }
+}
+
+/// Entry of the `size` x `size` Bayer matrix at `(x, y)`, built recursively from the 2x2 one.
+fn bayer_index(size: u32, x: u32, y: u32) -> u32 {
+    const BAYER_2: [[u32; 2]; 2] = [[0, 2], [3, 1]];
+    if size == 1 {
+        return 0;
+    }
+    let half = size / 2;
+    4 * bayer_index(half, x % half, y % half)
+        + BAYER_2[(y / half % 2) as usize][(x / half % 2) as usize]
+}
+
+/// Threshold in (0, 1) for the pixel at `(x, y)`, tiling the `size` x `size` Bayer matrix.
+fn bayer_threshold(size: u32, x: u32, y: u32) -> f32 {
+    (bayer_index(size, x % size, y % size) as f32 + 0.5) / (size * size) as f32
+}
+
+/// Width and height of the tiled blue noise texture, a divisor of [`STRIP_ALIGN`](crate::styling::STRIP_ALIGN)
+/// so that strips rendered separately line up.
+const BLUE_NOISE_SIZE: u32 = 48;
+
+/// Round each channel to 0 or 1 by comparing it to the blue noise texture for `seed`.
+fn blue_noise(image: &mut Rgb32FImage, seed: u64) {
+    let ranks = blue_noise_ranks(seed);
+    let cells = (BLUE_NOISE_SIZE * BLUE_NOISE_SIZE) as f32;
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let index = (y % BLUE_NOISE_SIZE * BLUE_NOISE_SIZE + x % BLUE_NOISE_SIZE) as usize;
+        let threshold = (ranks[index] as f32 + 0.5) / cells;
+        for value in pixel.0.iter_mut() {
+            *value = if *value > threshold { 1.0 } else { 0.0 };
+        }
+    }
+}
+
+/// Blue noise texture for `seed` as the rank of every pixel, generated once per seed.
+fn blue_noise_ranks(seed: u64) -> Arc<[u32]> {
+    static CACHE: Mutex<Option<(u64, Arc<[u32]>)>> = Mutex::new(None);
+    let mut cache = CACHE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    match &*cache {
+        Some((cached, ranks)) if *cached == seed => ranks.clone(),
+        _ => {
+            let ranks: Arc<[u32]> = void_and_cluster(seed).into();
+            *cache = Some((seed, ranks.clone()));
+            ranks
+        }
+    }
+}
+
+/// Rank pixels of a tileable square with Ulichney's void and cluster method: starting from
+/// a random pattern, minority pixels are moved from the tightest cluster to the largest void
+/// until evenly spread, then removed from clusters and added to voids one at a time, with each
+/// pixel ranked by when it was set. Any threshold of the ranks gives evenly spread pixels.
+fn void_and_cluster(seed: u64) -> Vec<u32> {
+    const SIGMA: f32 = 1.5;
+    let size = BLUE_NOISE_SIZE as usize;
+    let count = size * size;
+    // Gaussian weight of each offset, wrapping around so that the texture tiles.
+    let kernel: Vec<f32> = (0..count)
+        .map(|index| {
+            let wrap = |d: usize| d.min(size - d) as f32;
+            let (dx, dy) = (wrap(index % size), wrap(index / size));
+            (-(dx * dx + dy * dy) / (2.0 * SIGMA * SIGMA)).exp()
+        })
+        .collect();
+    let mut energy = vec![0.0; count];
+    let mut set = vec![false; count];
+    let toggle = |energy: &mut [f32], set: &mut [bool], index: usize| {
+        set[index] = !set[index];
+        let sign = if set[index] { 1.0 } else { -1.0 };
+        let (x, y) = (index % size, index / size);
+        for (other, energy) in energy.iter_mut().enumerate() {
+            let dx = (other % size + size - x) % size;
+            let dy = (other / size + size - y) % size;
+            *energy += sign * kernel[dy * size + dx];
+        }
+    };
+    // Tightest cluster among set pixels, or largest void among unset ones.
+    let extreme = |energy: &[f32], set: &[bool], cluster: bool| {
+        let candidates = (0..count).filter(|&index| set[index] == cluster);
+        let energy = |&index: &usize| energy[index];
+        if cluster {
+            candidates.max_by(|a, b| energy(a).total_cmp(&energy(b)))
+        } else {
+            candidates.min_by(|a, b| energy(a).total_cmp(&energy(b)))
+        }
+        .expect("pattern has pixels of both kinds")
+    };
+
+    let mut random = SplitMix64(seed);
+    let initial = count / 10;
+    let mut placed = 0;
+    while placed < initial {
+        let index = (random.next() % count as u64) as usize;
+        if !set[index] {
+            toggle(&mut energy, &mut set, index);
+            placed += 1;
+        }
+    }
+    loop {
+        let cluster = extreme(&energy, &set, true);
+        toggle(&mut energy, &mut set, cluster);
+        let void = extreme(&energy, &set, false);
+        toggle(&mut energy, &mut set, void);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut ranks = vec![0; count];
+    let (initial_energy, initial_set) = (energy.clone(), set.clone());
+    for rank in (0..initial).rev() {
+        let cluster = extreme(&energy, &set, true);
+        toggle(&mut energy, &mut set, cluster);
+        ranks[cluster] = rank as u32;
+    }
+    let (mut energy, mut set) = (initial_energy, initial_set);
+    for rank in initial..count {
+        let void = extreme(&energy, &set, false);
+        toggle(&mut energy, &mut set, void);
+        ranks[void] = rank as u32;
+    }
+    ranks
+}
+
+/// Small fast random number generator, enough to scatter the initial blue noise pattern.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Snap every channel to the levels of the 6x6x6 color cube of 256 color terminals,
+/// choosing between the two nearest levels with an ordered Bayer pattern.
+pub fn ordered_cube(image: &mut Rgb32FImage) {
+    let levels = CUBE_LEVELS.map(|level| level as f32 / 255.0);
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let threshold = bayer_threshold(4, x, y);
+        for c in pixel.0.iter_mut() {
+            let value = c.clamp(0.0, 1.0);
+            let upper = levels
+                .iter()
+                .position(|&level| level >= value)
+                .unwrap_or(5)
+                .max(1);
+            let (low, high) = (levels[upper - 1], levels[upper]);
+            let fraction = (value - low) / (high - low);
+            *c = if fraction > threshold { high } else { low };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn total(kernel: &Kernel) -> f32 {
+        kernel
+            .weights
+            .iter()
+            .map(|(_, _, weight)| weight)
+            .sum::<f32>()
+            / kernel.divisor
+    }
+
+    #[test]
+    fn kernels_spread_the_whole_error() {
+        for kernel in [&FLOYD_STEINBERG, &JARVIS_JUDICE_NINKE, &SIERRA, &STUCKI] {
+            assert_eq!(total(kernel), 1.0);
+        }
+        // Atkinson drops a quarter on purpose.
+        assert_eq!(total(&ATKINSON), 0.75);
+    }
+
+    #[test]
+    fn kernels_only_reach_unvisited_pixels() {
+        for kernel in [
+            &FLOYD_STEINBERG,
+            &ATKINSON,
+            &JARVIS_JUDICE_NINKE,
+            &SIERRA,
+            &STUCKI,
+        ] {
+            assert!(kernel.weights.iter().all(|&(dx, dy, _)| dy > 0 || dx > 0));
+        }
+    }
+}
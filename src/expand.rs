@@ -0,0 +1,191 @@
+use image::ImageFormat;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Expand a filename argument into the files to display, appending them to `paths`.
+/// Directories list the images directly inside them, or below them when `recursive`,
+/// and patterns with `*`, `?` or `[...]` are matched against existing files.
+/// Files found this way are skipped unless their extension names an image format.
+pub fn expand(argument: &str, recursive: bool, paths: &mut Vec<String>) -> io::Result<()> {
+    let path = Path::new(argument);
+    if argument == "-" || !is_pattern(argument) || path.exists() {
+        if path.is_dir() {
+            list(path, recursive, paths)?;
+        } else {
+            paths.push(argument.to_string());
+        }
+        return Ok(());
+    }
+    let matches = glob(path);
+    if matches.is_empty() {
+        // Keep the pattern so that it is reported as missing, like a shell without nullglob.
+        paths.push(argument.to_string());
+        return Ok(());
+    }
+    for path in matches {
+        if path.is_dir() {
+            list(&path, recursive, paths)?;
+        } else if is_image(&path) {
+            paths.push(path.to_string_lossy().into_owned());
+        }
+    }
+    Ok(())
+}
+
+/// Append the images in `dir` sorted by name, descending into subdirectories when `recursive`.
+/// Hidden entries are skipped and symbolic links to directories are not followed.
+fn list(dir: &Path, recursive: bool, paths: &mut Vec<String>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            if recursive {
+                list(&path, recursive, paths)?;
+            }
+        } else if is_image(&path) {
+            paths.push(path.to_string_lossy().into_owned());
+        }
+    }
+    Ok(())
+}
+
+/// Whether the extension of `path` names a supported image format.
+fn is_image(path: &Path) -> bool {
+    ImageFormat::from_path(path).is_ok_and(|format| format.reading_enabled())
+}
+
+fn is_pattern(s: &str) -> bool {
+    s.contains(['*', '?', '['])
+}
+
+/// Existing paths matching `pattern` component by component, sorted by name.
+fn glob(pattern: &Path) -> Vec<PathBuf> {
+    let mut matches = vec![PathBuf::new()];
+    for component in pattern.components() {
+        let part = component.as_os_str();
+        let Some(part) = part.to_str().filter(|part| is_pattern(part)) else {
+            matches.iter_mut().for_each(|path| path.push(part));
+            matches.retain(|path| path.exists());
+            continue;
+        };
+        let pattern: Vec<char> = part.chars().collect();
+        let mut next = Vec::new();
+        for dir in &matches {
+            let listed = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            let Ok(entries) = fs::read_dir(listed) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name();
+                let Some(name) = name.to_str() else {
+                    continue;
+                };
+                // Like shells, wildcards do not match hidden files unless the pattern starts with a dot.
+                if name.starts_with('.') && !part.starts_with('.') {
+                    continue;
+                }
+                let name: Vec<char> = name.chars().collect();
+                if matches_pattern(&pattern, &name) {
+                    next.push(dir.join(entry.file_name()));
+                }
+            }
+        }
+        next.sort();
+        matches = next;
+    }
+    matches
+}
+
+/// Match a single path component against a pattern of literal characters, `*` for any
+/// run of characters, `?` for any one character and `[...]` for one of a set or range,
+/// negated with a leading `!` or `^`.
+fn matches_pattern(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| matches_pattern(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && matches_pattern(rest, &name[1..]),
+        Some(('[', rest)) if let Some(end) = rest.iter().skip(1).position(|&c| c == ']') => {
+            let (class, rest) = (&rest[..end + 1], &rest[end + 2..]);
+            let Some((&c, name)) = name.split_first() else {
+                return false;
+            };
+            let (negated, class) = match class.split_first() {
+                Some(('!' | '^', class)) if !class.is_empty() => (true, class),
+                _ => (false, class),
+            };
+            let mut contained = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == '-' {
+                    contained |= (class[i]..=class[i + 2]).contains(&c);
+                    i += 3;
+                } else {
+                    contained |= class[i] == c;
+                    i += 1;
+                }
+            }
+            contained != negated && matches_pattern(rest, name)
+        }
+        Some((&literal, rest)) => {
+            name.first() == Some(&literal) && matches_pattern(rest, &name[1..])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Expand `pattern` inside `dir`, giving the paths relative to it.
+    fn expanded(dir: &Path, pattern: &str, recursive: bool) -> Vec<String> {
+        let mut paths = Vec::new();
+        let argument = dir.join(pattern);
+        expand(argument.to_str().unwrap(), recursive, &mut paths).unwrap();
+        let prefix = format!("{}/", dir.display());
+        paths
+            .iter()
+            .map(|path| path.strip_prefix(&prefix).unwrap_or(path).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn patterns_match_images_in_a_directory() {
+        let dir = std::env::temp_dir().join(format!("ttview-expand-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub/deep")).unwrap();
+        let names = [
+            "a.png",
+            "b.jpg",
+            "c.txt",
+            ".hidden.png",
+            "sub/d.png",
+            "sub/e.gif",
+        ];
+        for name in names.into_iter().chain(["sub/deep/f.png"]) {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        assert_eq!(expanded(&dir, "*.png", false), ["a.png"]);
+        assert_eq!(expanded(&dir, "?.*", false), ["a.png", "b.jpg"]);
+        assert_eq!(expanded(&dir, "[!a].*", false), ["b.jpg"]);
+        assert_eq!(expanded(&dir, "[a-b].*", false), ["a.png", "b.jpg"]);
+        assert_eq!(expanded(&dir, ".*.png", false), [".hidden.png"]);
+        assert_eq!(expanded(&dir, "*/*.png", false), ["sub/d.png"]);
+        // Matched directories show their images like directory arguments.
+        assert_eq!(expanded(&dir, "s*", false), ["sub/d.png", "sub/e.gif"]);
+        assert_eq!(
+            expanded(&dir, "s*", true),
+            ["sub/d.png", "sub/deep/f.png", "sub/e.gif"]
+        );
+        // Patterns without matches are kept to be reported as missing.
+        assert_eq!(expanded(&dir, "*.webp", false), ["*.webp"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,94 @@
+/// Whether a filename argument is a URL to download instead of a path.
+pub fn is_url(argument: &str) -> bool {
+    argument.starts_with("http://") || argument.starts_with("https://")
+}
+
+/// Largest image downloaded, far beyond real images but keeping a broken server from
+/// filling up memory.
+#[cfg(feature = "net")]
+const MAX_SIZE: u64 = 1 << 30;
+
+/// Download the image at `url`, following redirects and giving up after `timeout` seconds.
+/// Error statuses and responses that are not images fail.
+#[cfg(feature = "net")]
+pub fn download(url: &str, timeout: u32) -> Result<Vec<u8>, String> {
+    use std::time::Duration;
+
+    let agent = ureq::Agent::config_builder()
+        .max_redirects(10)
+        .timeout_global(Some(Duration::from_secs(timeout as u64)))
+        .build()
+        .new_agent();
+    let mut response = agent
+        .get(url)
+        .call()
+        .map_err(|err| format!("download failed: {err}"))?;
+    // Servers without a specific type are given the benefit of the doubt.
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("");
+    let mime = content_type.split(';').next().unwrap_or("").trim();
+    if !(mime.is_empty() || mime == "application/octet-stream" || mime.starts_with("image/")) {
+        return Err(format!("expected an image, the server sent {mime}"));
+    }
+    response
+        .body_mut()
+        .with_config()
+        .limit(MAX_SIZE)
+        .read_to_vec()
+        .map_err(|err| format!("download failed: {err}"))
+}
+
+#[cfg(not(feature = "net"))]
+pub fn download(_url: &str, _timeout: u32) -> Result<Vec<u8>, String> {
+    Err("downloading images needs ttview built with the `net` feature".to_string())
+}
+
+#[cfg(all(test, feature = "net"))]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// Serve a single request on a local port with `response`, returning the URL to request.
+    fn serve(response: &'static [u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/image.png", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut line = String::new();
+            // Read the request up to the blank line after its headers.
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            reader.get_mut().write_all(response).unwrap();
+        });
+        url
+    }
+
+    #[test]
+    fn downloads_images() {
+        let url =
+            serve(b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: 4\r\n\r\nPNG!");
+        assert_eq!(download(&url, 5).unwrap(), b"PNG!");
+    }
+
+    #[test]
+    fn rejects_other_content() {
+        let url = serve(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: 2\r\n\r\nhi",
+        );
+        let err = download(&url, 5).unwrap_err();
+        assert_eq!(err, "expected an image, the server sent text/html");
+    }
+
+    #[test]
+    fn fails_on_error_status() {
+        let url = serve(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+        let err = download(&url, 5).unwrap_err();
+        assert!(err.contains("404"), "{err}");
+    }
+}
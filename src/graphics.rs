@@ -0,0 +1,170 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use color_quant::NeuQuant;
+use image::{DynamicImage, GrayImage, ImageFormat, Rgb32FImage, Rgba, RgbaImage};
+use std::fmt::Write;
+use std::io::Cursor;
+
+/// How images are drawn in the terminal.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Protocol {
+    /// Approximate the image with colored characters in the chosen style.
+    #[default]
+    Text,
+    /// Sixel graphics, supported by foot, xterm, mlterm and WezTerm among others.
+    Sixel,
+    /// Kitty graphics protocol, supported by Kitty, WezTerm and Ghostty.
+    Kitty,
+}
+
+impl Protocol {
+    /// Whether the protocol draws actual pixels instead of characters.
+    pub fn is_graphics(&self) -> bool {
+        !matches!(self, Self::Text)
+    }
+
+    /// Escape sequence drawing the image over `cells` as `(columns, rows)`, with the opacity
+    /// of every pixel in `alpha` if the image has transparent parts. For graphics protocols only.
+    pub fn encode(
+        &self,
+        image: &Rgb32FImage,
+        alpha: Option<&GrayImage>,
+        cells: (u32, u32),
+    ) -> String {
+        match self {
+            Self::Text => unreachable!("text is drawn by styles"),
+            Self::Sixel => sixel(image, alpha),
+            Self::Kitty => kitty(image, alpha, cells),
+        }
+    }
+}
+
+/// Wrap each escape sequence of graphics `output` for tmux to pass it through to the
+/// outer terminal, which needs `set -g allow-passthrough on` since tmux 3.3.
+pub fn tmux_passthrough(output: &str) -> String {
+    let mut wrapped = String::with_capacity(output.len() * 2);
+    // Sixel and kitty sequences all end with the string terminator.
+    for sequence in output.split_inclusive("\x1B\\") {
+        wrapped += "\x1BPtmux;";
+        wrapped += &sequence.replace('\x1B', "\x1B\x1B");
+        wrapped += "\x1B\\";
+    }
+    wrapped
+}
+
+/// Number of palette registers used for sixel output.
+const SIXEL_COLORS: usize = 256;
+
+/// Encode the image as a sixel sequence with a palette quantized from its colors.
+/// Sixels are either drawn or not, so pixels less than half opaque in `alpha` are left out
+/// and show what was on the screen before.
+pub fn sixel(image: &Rgb32FImage, alpha: Option<&GrayImage>) -> String {
+    let image = DynamicImage::ImageRgb32F(image.clone()).to_rgba8();
+    let (width, height) = image.dimensions();
+    let quantizer = NeuQuant::new(10, SIXEL_COLORS, image.as_raw());
+    let indices: Vec<Option<usize>> = image
+        .enumerate_pixels()
+        .map(|(x, y, pixel)| {
+            let opaque = alpha.is_none_or(|alpha| alpha.get_pixel(x, y)[0] >= 128);
+            opaque.then(|| quantizer.index_of(&pixel.0))
+        })
+        .collect();
+
+    // Introducer, with transparent pixels keeping their color if there are any, then raster
+    // attributes declaring square pixels and the image size.
+    let background = if alpha.is_some() { "0;1" } else { "" };
+    let mut output = format!("\x1BP{background}q\"1;1;{width};{height}");
+    for (index, color) in quantizer.color_map_rgb().chunks(3).enumerate() {
+        let [r, g, b] = [0, 1, 2].map(|c| color[c] as u32 * 100 / 255);
+        write!(output, "#{index};2;{r};{g};{b}").unwrap();
+    }
+    // Each band covers six rows, one bit per row, drawn in one pass per color.
+    for top in (0..height).step_by(6) {
+        let rows = top..(top + 6).min(height);
+        let mut colors: Vec<usize> = rows
+            .clone()
+            .flat_map(|y| &indices[(y * width) as usize..((y + 1) * width) as usize])
+            .flatten()
+            .copied()
+            .collect();
+        colors.sort_unstable();
+        colors.dedup();
+        for (pass, color) in colors.into_iter().enumerate() {
+            if pass > 0 {
+                output.push('$');
+            }
+            write!(output, "#{color}").unwrap();
+            let sixels = (0..width).map(|x| {
+                let bits = rows
+                    .clone()
+                    .filter(|y| indices[(y * width + x) as usize] == Some(color))
+                    .fold(0, |bits, y| bits | 1 << (y - top));
+                char::from(63 + bits as u8)
+            });
+            push_runs(&mut output, sixels);
+        }
+        output.push('-');
+    }
+    output += "\x1B\\";
+    output
+}
+
+/// Append the sixels, compressing repeats with the `!` run length introducer.
+fn push_runs(output: &mut String, sixels: impl Iterator<Item = char>) {
+    let mut sixels = sixels.peekable();
+    while let Some(sixel) = sixels.next() {
+        let mut count = 1;
+        while sixels.next_if_eq(&sixel).is_some() {
+            count += 1;
+        }
+        if count > 3 {
+            write!(output, "!{count}{sixel}").unwrap();
+        } else {
+            (0..count).for_each(|_| output.push(sixel));
+        }
+    }
+}
+
+/// Largest payload the kitty protocol accepts in a single escape sequence.
+const KITTY_CHUNK: usize = 4096;
+
+/// Transmit the image as a PNG and display it over `columns` by `rows` cells,
+/// split into chunks that each but the last mark with `m=1`. The PNG carries the opacity
+/// of each pixel from `alpha` if given.
+pub fn kitty(
+    image: &Rgb32FImage,
+    alpha: Option<&GrayImage>,
+    (columns, rows): (u32, u32),
+) -> String {
+    let rgb = DynamicImage::ImageRgb32F(image.clone()).to_rgb8();
+    let png_image = match alpha {
+        Some(alpha) => {
+            let rgba = RgbaImage::from_fn(rgb.width(), rgb.height(), |x, y| {
+                let [r, g, b] = rgb.get_pixel(x, y).0;
+                Rgba([r, g, b, alpha.get_pixel(x, y)[0]])
+            });
+            DynamicImage::ImageRgba8(rgba)
+        }
+        None => DynamicImage::ImageRgb8(rgb),
+    };
+    let mut png = Vec::new();
+    png_image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .expect("encoding to memory cannot fail");
+    let payload = STANDARD.encode(png);
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK).collect();
+    let mut output = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        // Control data is only read from the first chunk, q=2 suppresses responses.
+        let control = if i == 0 {
+            format!("a=T,f=100,q=2,c={columns},r={rows},")
+        } else {
+            String::new()
+        };
+        let more = u8::from(i + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk).expect("base64 is ASCII");
+        write!(output, "\x1B_G{control}m={more};{chunk}\x1B\\").unwrap();
+    }
+    output
+}
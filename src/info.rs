@@ -0,0 +1,166 @@
+use image::{ColorType, ImageFormat};
+use std::fmt::Write;
+
+/// EXIF tags of the fields shown, all stored as ASCII strings.
+const MAKE: u16 = 0x010F;
+const MODEL: u16 = 0x0110;
+const DATE_TIME: u16 = 0x0132;
+const EXIF_IFD: u16 = 0x8769;
+const DATE_TIME_ORIGINAL: u16 = 0x9003;
+
+/// Lines describing the source of an image, indented to go below its file name.
+pub fn describe(
+    format: Option<ImageFormat>,
+    dimensions: (u32, u32),
+    color: ColorType,
+    file_size: u64,
+    exif: Option<&[u8]>,
+) -> String {
+    let format = match format {
+        Some(format) => format!("{format:?}").to_lowercase(),
+        None => "raw".to_string(),
+    };
+    let (width, height) = dimensions;
+    let color = format!("{color:?}").to_lowercase();
+    let mut info = format!(
+        "  {format}, {width}x{height}, {color}, {}\n",
+        bytes(file_size)
+    );
+    let tags = exif.map(Tags::parse).unwrap_or_default();
+    let camera = match (tags.make, tags.model) {
+        // Models usually repeat the make already.
+        (Some(make), Some(model)) if model.starts_with(&make) => Some(model),
+        (Some(make), Some(model)) => Some(format!("{make} {model}")),
+        (make, model) => make.or(model),
+    };
+    if let Some(camera) = camera {
+        writeln!(info, "  camera: {camera}").unwrap();
+    }
+    if let Some(date) = tags.date {
+        writeln!(info, "  taken: {date}").unwrap();
+    }
+    info
+}
+
+/// File size in the largest binary unit that keeps it at least 1.
+fn bytes(size: u64) -> String {
+    let mut value = size as f64;
+    for unit in ["B", "KiB", "MiB"] {
+        if value < 1024.0 {
+            return if unit == "B" {
+                format!("{size} B")
+            } else {
+                format!("{value:.1} {unit}")
+            };
+        }
+        value /= 1024.0;
+    }
+    format!("{value:.1} GiB")
+}
+
+/// Fields read from an EXIF block.
+#[derive(Debug, Default)]
+struct Tags {
+    make: Option<String>,
+    model: Option<String>,
+    date: Option<String>,
+}
+
+impl Tags {
+    /// Read the fields from a TIFF structured EXIF block, skipping anything malformed.
+    fn parse(exif: &[u8]) -> Self {
+        let mut tags = Self::default();
+        let Some(tiff) = Tiff::new(exif) else {
+            return tags;
+        };
+        let mut exif_ifd = None;
+        let mut modified = None;
+        let mut original = None;
+        let ifd0 = tiff.u32(4);
+        tiff.entries(ifd0, |tag, entry| match tag {
+            MAKE => tags.make = tiff.ascii(entry),
+            MODEL => tags.model = tiff.ascii(entry),
+            DATE_TIME => modified = tiff.ascii(entry),
+            EXIF_IFD => exif_ifd = tiff.u32(entry + 8),
+            _ => {}
+        });
+        if let Some(offset) = exif_ifd {
+            tiff.entries(Some(offset), |tag, entry| {
+                if tag == DATE_TIME_ORIGINAL {
+                    original = tiff.ascii(entry);
+                }
+            });
+        }
+        // The original date is when the photo was taken, the other one when it was last edited.
+        tags.date = original.or(modified);
+        tags
+    }
+}
+
+/// TIFF header and byte order of an EXIF block.
+struct Tiff<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let big_endian = match data.get(..4)? {
+            b"MM\0*" => true,
+            b"II*\0" => false,
+            _ => return None,
+        };
+        Some(Self { data, big_endian })
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes = self.data.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes = self.data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    /// Call `visit` with the tag and offset of each 12 byte entry in the IFD at `offset`.
+    fn entries(&self, offset: Option<u32>, mut visit: impl FnMut(u16, usize)) {
+        let Some(offset) = offset else { return };
+        let offset = offset as usize;
+        let count = self.u16(offset).unwrap_or(0) as usize;
+        for i in 0..count {
+            let entry = offset + 2 + i * 12;
+            match self.u16(entry) {
+                Some(tag) => visit(tag, entry),
+                None => return,
+            }
+        }
+    }
+
+    /// Value of an ASCII entry without its terminating nul and surrounding spaces.
+    fn ascii(&self, entry: usize) -> Option<String> {
+        const ASCII: u16 = 2;
+        if self.u16(entry + 2)? != ASCII {
+            return None;
+        }
+        let count = self.u32(entry + 4)? as usize;
+        // Values of up to four bytes are stored in the entry itself.
+        let offset = if count <= 4 {
+            entry + 8
+        } else {
+            self.u32(entry + 8)? as usize
+        };
+        let value = self.data.get(offset..offset.checked_add(count)?)?;
+        let value = String::from_utf8_lossy(value);
+        let value = value.trim_end_matches('\0').trim();
+        (!value.is_empty()).then(|| value.to_string())
+    }
+}
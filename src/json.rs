@@ -0,0 +1,24 @@
+//! JSON encoding for reports and cell output, which only ever needs strings and numbers.
+
+use std::fmt::Write;
+
+/// Quote and escape a string as a JSON string literal.
+pub fn string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted += "\\\"",
+            '\\' => quoted += "\\\\",
+            '\n' => quoted += "\\n",
+            '\r' => quoted += "\\r",
+            '\t' => quoted += "\\t",
+            c if c.is_control() => {
+                write!(quoted, "\\u{:04x}", c as u32).expect("failed to write to string")
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
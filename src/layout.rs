@@ -0,0 +1,71 @@
+use unicode_width::UnicodeWidthChar;
+
+/// Number of columns a line of output takes in the terminal, skipping escape sequences.
+/// Wide characters such as CJK in file names take two.
+pub fn visible_width(line: &str) -> usize {
+    let mut width = 0;
+    let mut escape = false;
+    for c in line.chars() {
+        match c {
+            '\x1B' => escape = true,
+            // SGR sequences, the only ones in rendered text, end with `m`.
+            'm' if escape => escape = false,
+            _ if escape => {}
+            c => width += c.width().unwrap_or(0),
+        }
+    }
+    width
+}
+
+/// Width of the widest line of a block of text.
+pub fn block_width(block: &str) -> usize {
+    block.lines().map(visible_width).max().unwrap_or(0)
+}
+
+/// Lay out blocks of text side by side, `gap` spaces apart. Each block is padded to its
+/// entry in `widths` and shorter blocks continue with blank lines.
+pub fn side_by_side(blocks: &[&str], widths: &[usize], gap: usize) -> String {
+    let lines: Vec<Vec<&str>> = blocks.iter().map(|block| block.lines().collect()).collect();
+    let height = lines.iter().map(Vec::len).max().unwrap_or(0);
+    let mut output = String::new();
+    for row in 0..height {
+        let mut pending = 0;
+        for (block, &width) in lines.iter().zip(widths) {
+            let line = block.get(row).copied().unwrap_or("");
+            // Padding is only written before the next non-empty line, never at the end.
+            if !line.is_empty() {
+                output.extend(std::iter::repeat_n(' ', pending));
+                output += line;
+                pending = 0;
+            }
+            pending += width.saturating_sub(visible_width(line)) + gap;
+        }
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn montage_cells_line_up() {
+        // Two rows of cells four columns wide, images narrower than the cell included.
+        let widths = [4, 4];
+        let top = side_by_side(
+            &["\x1B[31m██\x1B[0m\n\x1B[31m██\x1B[0m", "████\n████"],
+            &widths,
+            2,
+        );
+        let bottom = side_by_side(&["▀▀▀▀", "████\n████"], &widths, 2);
+        assert_eq!(
+            top,
+            "\x1B[31m██\x1B[0m    ████\n\x1B[31m██\x1B[0m    ████\n"
+        );
+        assert_eq!(bottom, "▀▀▀▀  ████\n      ████\n");
+        for line in top.lines().chain(bottom.lines()) {
+            assert_eq!(visible_width(line), 10, "{line:?}");
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! Render images as text with escape sequences for display in a terminal.
+//!
+//! The command line interface is behind the default `cli` feature, disable default features
+//! to use the library without pulling in `clap`.
+
+use crate::compositing::{Background, composite};
+use crate::styling::RenderOptions;
+use image::DynamicImage;
+
+pub mod adjusting;
+pub mod colors;
+pub mod compositing;
+pub mod dithering;
+pub mod graphics;
+pub mod json;
+pub mod raster;
+pub mod resizing;
+pub mod styling;
+pub mod terminal;
+
+pub use resizing::Filter;
+pub use styling::Style;
+
+/// Single pixel value.
+pub type Pixel = image::Rgb<f32>;
+
+/// Width to height ratio of a typical terminal cell.
+pub const DEFAULT_CELL_ASPECT: f32 = 0.5;
+
+/// Width in pixels images are rendered at when no dimensions are given.
+const DEFAULT_WIDTH: u32 = 80;
+
+/// Render the image in `style` after resizing it with `filter` to `dims`, the image size in
+/// pixels as `(width, height)`. With only one of them the other keeps the aspect ratio,
+/// with neither the image is 80 pixels wide. Transparent regions show black.
+/// [`Style::Auto`] renders like [`Style::Color`], as the library does not detect the terminal.
+pub fn render(
+    image: &DynamicImage,
+    style: &Style,
+    filter: Filter,
+    dims: (Option<u32>, Option<u32>),
+) -> String {
+    let pixel_aspect = style.pixel_aspect(DEFAULT_CELL_ASPECT);
+    let resized = resizing::resize(image.clone(), or_default(dims), filter, pixel_aspect);
+    let background = Background::Solid(Pixel::from([0.0; 3]));
+    let mut image = composite(&resized.to_rgba32f(), &background);
+    style.apply_to_string(&mut image, &RenderOptions::default())
+}
+
+/// Number of terminal `(columns, rows)` that [`render`] takes for an image of `image_dims`
+/// pixels, without rendering it, e.g. to reserve space for it in a layout.
+pub fn rendered_size(
+    image_dims: (u32, u32),
+    style: &Style,
+    dims: (Option<u32>, Option<u32>),
+) -> (u32, u32) {
+    let pixel_aspect = style.pixel_aspect(DEFAULT_CELL_ASPECT);
+    let resized = resizing::target_dimensions(image_dims, or_default(dims), pixel_aspect);
+    style.cell_count(resized)
+}
+
+/// Requested dimensions, or the default width without any.
+fn or_default(dims: (Option<u32>, Option<u32>)) -> (Option<u32>, Option<u32>) {
+    match dims {
+        (None, None) => (Some(DEFAULT_WIDTH), None),
+        dims => dims,
+    }
+}
@@ -1,47 +1,679 @@
-use clap::Parser;
-use image::{DynamicImage, ImageReader, Rgb};
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches};
+use image::codecs::pnm::{PnmEncoder, PnmSubtype, SampleEncoding};
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageFormat, ImageReader, Rgb32FImage, RgbImage, RgbaImage};
+use image::{GenericImageView, GrayImage, ImageDecoder};
 use std::fmt::{Display, Formatter};
-use std::path::Path;
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Cursor, IsTerminal, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::str::FromStr;
 
-mod resizing;
-mod styling;
+mod animation;
+mod asciicast;
+mod bench;
+mod config;
+mod expand;
+mod fetch;
+mod info;
+mod layout;
+mod overrides;
+mod report;
 
+use ttview::{
+    DEFAULT_CELL_ASPECT, Pixel, adjusting, colors, compositing, dithering, graphics, resizing,
+    styling, terminal,
+};
+
+use adjusting::*;
+use asciicast::*;
+use colors::*;
+use compositing::*;
+use config::Config;
+use dithering::Dither;
+use graphics::{Protocol, tmux_passthrough};
+use layout::{block_width, side_by_side, visible_width};
 use resizing::*;
 use styling::*;
+use terminal::*;
+
+/// Format of image data read from stdin.
+#[derive(Debug, Default, Copy, Clone, clap::ValueEnum)]
+enum StdinFormat {
+    /// Image in any supported container format.
+    #[default]
+    Encoded,
+
+    /// Headerless 8 bit pixel data, see `--raw-size` and `--raw-channels`.
+    Raw,
+}
+
+/// What the rendered image is written as.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+enum OutputFormat {
+    /// Text with escape sequences for the terminal.
+    #[default]
+    Text,
+
+    /// JSON array of the rendered lines of cells, see `Style::apply_json`.
+    /// Needs ttview built with the `json` feature.
+    Json,
+}
+
+/// Dimensions of raw pixel data.
+#[derive(Debug, Copy, Clone)]
+struct Size {
+    width: u32,
+    height: u32,
+}
 
-/// Single pixel value.
-type Pixel = Rgb<f32>;
+impl FromStr for Size {
+    type Err = String;
 
-#[derive(clap::Parser, Debug)]
+    /// Parse a size from `WxH`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, height) = s
+            .split_once('x')
+            .ok_or_else(|| format!("invalid size '{s}', expected WxH"))?;
+        let parse = |value: &str| {
+            value
+                .parse::<u32>()
+                .map_err(|_| format!("invalid size '{s}', expected WxH"))
+        };
+        Ok(Self {
+            width: parse(width)?,
+            height: parse(height)?,
+        })
+    }
+}
+
+/// Number of images across and down a montage.
+#[derive(Debug, Copy, Clone)]
+struct Grid {
+    columns: u32,
+    rows: u32,
+}
+
+impl FromStr for Grid {
+    type Err = String;
+
+    /// Parse a grid from `COLSxROWS`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("invalid grid '{s}', expected COLSxROWS with positive numbers");
+        let (columns, rows) = s.split_once('x').ok_or_else(error)?;
+        let parse = |value: &str| {
+            value
+                .parse::<u32>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(error)
+        };
+        Ok(Self {
+            columns: parse(columns)?,
+            rows: parse(rows)?,
+        })
+    }
+}
+
+/// Rectangle of the source image to show, in pixels.
+#[derive(Debug, Copy, Clone)]
+struct Crop {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Crop {
+    /// Whether the rectangle lies within an image of the given dimensions.
+    fn fits(&self, (width, height): (u32, u32)) -> bool {
+        self.x
+            .checked_add(self.width)
+            .is_some_and(|right| right <= width)
+            && self
+                .y
+                .checked_add(self.height)
+                .is_some_and(|bottom| bottom <= height)
+    }
+
+    fn apply(&self, image: &DynamicImage) -> DynamicImage {
+        image.crop_imm(self.x, self.y, self.width, self.height)
+    }
+}
+
+impl FromStr for Crop {
+    type Err = String;
+
+    /// Parse a crop rectangle from `X,Y,W,H`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid crop '{s}', expected X,Y,W,H");
+        let values = s
+            .split(',')
+            .map(|value| value.trim().parse::<u32>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let [x, y, width, height] = values[..] else {
+            return Err(invalid());
+        };
+        if width == 0 || height == 0 {
+            return Err(format!("crop '{s}' must have a width and height"));
+        }
+        Ok(Self {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+}
+
+impl Display for Crop {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{},{},{}", self.x, self.y, self.width, self.height)
+    }
+}
+
+/// Clockwise rotation of images.
+#[derive(Debug, Copy, Clone, clap::ValueEnum)]
+enum Rotation {
+    #[value(name = "90")]
+    Quarter,
+
+    #[value(name = "180")]
+    Half,
+
+    #[value(name = "270")]
+    ThreeQuarters,
+}
+
+/// Direction to mirror images in.
+#[derive(Debug, Copy, Clone, clap::ValueEnum)]
+enum Flip {
+    /// Swap left and right.
+    Horizontal,
+
+    /// Swap top and bottom.
+    Vertical,
+}
+
+fn parse_zoom(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(factor) if factor > 0.0 && factor.is_finite() => Ok(factor),
+        _ => Err(format!(
+            "invalid zoom factor '{s}', expected a positive number"
+        )),
+    }
+}
+
+fn parse_factor(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(factor) if factor >= 0.0 && factor.is_finite() => Ok(factor),
+        _ => Err(format!(
+            "invalid factor '{s}', expected a number of at least 0"
+        )),
+    }
+}
+
+fn parse_gamma(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(gamma) if gamma > 0.0 && gamma.is_finite() => Ok(gamma),
+        _ => Err(format!("invalid gamma '{s}', expected a positive number")),
+    }
+}
+
+fn parse_cell_aspect(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(aspect) if aspect > 0.0 && aspect.is_finite() => Ok(aspect),
+        _ => Err(format!(
+            "invalid cell aspect '{s}', expected a positive number"
+        )),
+    }
+}
+
+fn parse_brightness(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(offset) if (-1.0..=1.0).contains(&offset) => Ok(offset),
+        _ => Err(format!(
+            "invalid brightness '{s}', expected a number from -1 to 1"
+        )),
+    }
+}
+
+fn parse_threshold(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(threshold) if (0.0..=1.0).contains(&threshold) => Ok(threshold),
+        _ => Err(format!(
+            "invalid threshold '{s}', expected a number from 0 to 1"
+        )),
+    }
+}
+
+fn parse_gradient(s: &str) -> Result<String, String> {
+    if s.is_empty() {
+        return Err("gradient needs at least one character".to_string());
+    }
+    if let Some(c) = non_single_width(s) {
+        return Err(format!(
+            "gradient characters must be one column wide, '{c}' (U+{:04X}) is not",
+            c as u32
+        ));
+    }
+    Ok(s.to_string())
+}
+
+#[derive(clap::Subcommand, Debug, Clone)]
+enum Command {
+    /// Render an image with every style and filter and print how long each took.
+    Bench {
+        /// File to benchmark.
+        filename: String,
+    },
+}
+
+#[derive(clap::Parser, Debug, Clone)]
 struct Args {
-    /// Files to display.
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    /// Files to display. Use `-` to read from stdin, or an http(s) URL to download an image
+    /// when built with the `net` feature.
+    /// Directories show the images inside them and patterns like `*.png` are expanded.
+    /// Settings for a single file follow its name, as in `photo.png:style=braille,width=40`
+    /// with any of style, gradient, filter, width and height.
     filenames: Vec<String>,
 
+    /// Seconds to wait for an image download before giving up.
+    #[clap(long, value_name = "SECONDS", default_value_t = 30, value_parser = clap::value_parser!(u32).range(1..))]
+    timeout: u32,
+
+    /// Also show images in subdirectories of directories given as files.
+    #[clap(short, long)]
+    recursive: bool,
+
     /// Optional width to scale the image to before displaying it.
     /// When height is also given, aspect ratio is not preserved.
-    /// When neither are given, a default width of 80 is used.
-    #[clap(short, long)]
+    /// When neither are given, the image is fit to the terminal, or to the `COLUMNS` and
+    /// `LINES` environment variables or 80 columns when output is not a terminal.
+    #[clap(short, long, value_parser = clap::value_parser!(u32).range(1..))]
     width: Option<u32>,
 
     /// Optional height to scale the image to before displaying it.
     /// When width is also given, aspect ratio is not preserved.
-    #[clap(short = 'y', long)]
+    #[clap(short = 'y', long, value_parser = clap::value_parser!(u32).range(1..))]
     height: Option<u32>,
 
+    /// Widest output in columns, wider images are scaled down to it. Defaults to the
+    /// terminal width when output is a terminal.
+    #[clap(long, value_name = "COLUMNS", value_parser = clap::value_parser!(u32).range(1..))]
+    max_width: Option<u32>,
+
+    /// Render at the requested size even when wider than the terminal or `--max-width`.
+    #[clap(long)]
+    force: bool,
+
+    /// Show only this region of the image, given as `X,Y,W,H` in pixels of the original image.
+    /// Width, height and the other sizing options apply to the cropped region.
+    #[clap(long, value_name = "X,Y,W,H")]
+    crop: Option<Crop>,
+
+    /// Rotate images clockwise by this many degrees, after cropping.
+    #[clap(long, value_enum, value_name = "DEGREES")]
+    rotate: Option<Rotation>,
+
+    /// Mirror images, after cropping and rotating.
+    #[clap(long, value_enum)]
+    flip: Option<Flip>,
+
+    /// Scale the image by this factor instead of to a target width or height, e.g. 0.5 or 2.
+    #[clap(long, value_parser = parse_zoom, conflicts_with_all = ["width", "height"])]
+    zoom: Option<f32>,
+
+    /// How to scale the image into the box given by width and height, or the terminal when
+    /// they are not given. `--fit` alone is `--fit=contain`, which fits the image within the
+    /// terminal or centers it in the box with borders in the background color. Without this
+    /// option an image fits the terminal and is stretched to a box, except for mono which
+    /// keeps its exact pixel grid.
+    #[clap(
+        long,
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "contain",
+        conflicts_with = "zoom"
+    )]
+    fit: Option<Fit>,
+
+    /// Width to height ratio of a terminal cell, used to keep images in proportion.
+    #[clap(long, default_value_t = DEFAULT_CELL_ASPECT, value_parser = parse_cell_aspect)]
+    cell_aspect: f32,
+
     /// Optional filter to use for scaling.
     #[clap(short, long)]
     filter: Option<Filter>,
 
+    /// Remove salt-and-pepper noise with a 3x3 median filter before resizing.
+    #[clap(long)]
+    despeckle: bool,
+
+    /// Average blocks of N x N source pixels before resizing, for a mosaic effect.
+    #[clap(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pixelate: Option<u32>,
+
+    /// Keep the hard edges of pixel art and small sprites by scaling with the nearest
+    /// neighbor filter by a whole factor, so that all pixels come out the same size.
+    #[clap(long, conflicts_with_all = ["filter", "sample_point"])]
+    pixel_art: bool,
+
+    /// Point-sample the image instead of resizing it with a filter.
+    /// Fast for tiny previews, but aliases on detailed images.
+    #[clap(long, conflicts_with = "filter")]
+    sample_point: bool,
+
     /// Optional display style.
     #[clap(short, long, group = "display_style", default_value = "color")]
     style: Style,
 
+    /// Write graphics as they are inside tmux instead of wrapping them to pass through it.
+    #[clap(long)]
+    no_tmux_passthrough: bool,
+
+    /// Character the color style draws with. Full blocks and spaces show the average of
+    /// both pixels of a cell, for terminals that draw half blocks with gaps or seams.
+    #[clap(long, value_enum, default_value_t)]
+    glyph: Glyph,
+
+    /// Draw actual pixels with a terminal graphics protocol instead of characters.
+    /// Width and height are still given in terminal cells.
+    #[clap(long, value_enum, default_value_t)]
+    protocol: Protocol,
+
+    /// Blend transparent images onto the background before drawing them with a graphics
+    /// protocol, for terminals that ignore transparency. Otherwise the terminal shows through.
+    #[clap(long)]
+    flatten: bool,
+
+    /// Fill the whole terminal window in pixels with a graphics protocol, as the terminal
+    /// reports its size, instead of whole cells below the file name. Terminals that do not
+    /// report their size in pixels are filled in cells as usual.
+    #[clap(long, conflicts_with_all = ["width", "height", "zoom", "montage", "columns"])]
+    fullscreen_pixels: bool,
+
+    /// Compute brightness directly from gamma encoded sRGB values as older versions did,
+    /// instead of from linear light.
+    #[clap(long)]
+    gamma_brightness: bool,
+
+    /// Weights of the color channels in brightness, used by every style that shows it.
+    /// Defaults to rec709, or rec601 with `--gamma-brightness` as in older versions.
+    #[clap(long, value_enum)]
+    luma: Option<Luma>,
+
+    /// Colors to use in escape sequences, 256 for terminals without true color.
+    #[clap(long, value_enum, default_value_t)]
+    colors: ColorDepth,
+
     /// Gradient string to use.
     /// First is darkest, right is the lightest color.
     /// Cannot be combined with another style.
-    #[clap(short, long, group = "display_style")]
+    #[clap(short, long, group = "display_style", value_parser = parse_gradient)]
     gradient: Option<String>,
 
+    /// Named gradient to use instead of giving its characters.
+    /// Cannot be combined with another style.
+    #[clap(long, value_enum, value_name = "NAME", group = "display_style")]
+    gradient_preset: Option<GradientPreset>,
+
+    /// Show brightness in false color, interpolating between colors given as
+    /// `RRGGBB-RRGGBB-...` from dark to bright, e.g. `0000ff-ffffff-ff0000` for a heatmap.
+    /// Cannot be combined with another style.
+    #[clap(
+        long,
+        group = "display_style",
+        value_name = "COLORS",
+        value_parser = parse_hex_list
+    )]
+    color_gradient: Option<::std::vec::Vec<Pixel>>,
+
+    /// Print plain text without escape sequences, in the gradient style with an ASCII ramp
+    /// unless a gradient is given. Also enabled by setting the `NO_COLOR` environment variable.
+    #[clap(long)]
+    no_color: bool,
+
+    /// Render gradients in the braille style by lighting a proportional, evenly spread
+    /// set of dots per cell instead of using a single brightness threshold.
+    #[clap(long)]
+    braille_gradient: bool,
+
+    /// Brightness from 0 to 1 at which braille styles switch dots, lower for dark images.
+    #[clap(long, default_value_t = 0.5, value_parser = parse_threshold)]
+    threshold: f32,
+
+    /// Dither algorithm for the dithered styles.
+    #[clap(long, value_enum, default_value_t)]
+    dither: Dither,
+
+    /// Alternate the direction of error diffusion every row, avoiding directional artifacts.
+    #[clap(long)]
+    serpentine: bool,
+
+    /// Seed of the texture for `--dither blue-noise`, the same seed gives the same output.
+    #[clap(long, value_name = "N", default_value_t = 0)]
+    seed: u64,
+
+    /// Append swatches of the colors used by palette-reduced styles such as `bayer-color`.
+    #[clap(long)]
+    show_palette: bool,
+
+    /// Checkerboard to show behind transparent regions, given as `W,H,RRGGBB,RRGGBB`.
+    /// Tiles are W x H pixels of the resized image and alternate between both colors.
+    #[clap(long, value_name = "W,H,COLOR1,COLOR2")]
+    tile_background: Option<Background>,
+
+    /// Color to show behind transparent regions, given as `RRGGBB`. Defaults to black.
+    #[clap(
+        long,
+        value_name = "RRGGBB",
+        value_parser = parse_hex,
+        conflicts_with = "tile_background"
+    )]
+    background: Option<Pixel>,
+
+    /// Format of the image data read from stdin.
+    #[clap(long, default_value = "encoded")]
+    stdin_format: StdinFormat,
+
+    /// Dimensions of raw pixel data read from stdin, given as `WxH`.
+    #[clap(long, value_name = "WxH", required_if_eq("stdin_format", "raw"))]
+    raw_size: Option<Size>,
+
+    /// Number of channels in raw pixel data read from stdin, 3 for RGB or 4 for RGBA.
+    #[clap(long, default_value_t = 3, value_parser = clap::value_parser!(u8).range(3..=4))]
+    raw_channels: u8,
+
+    /// Produce byte-identical output across machines, e.g. for golden-file tests.
+    /// Disables all environment-dependent behavior and requires an explicit width.
+    /// Animations show their first frame instead of playing, except in recordings.
+    #[clap(long, requires = "width")]
+    reproducible: bool,
+
+    /// Only show pixels close to this color, given as `RRGGBB`.
+    /// Other pixels become transparent and show the background.
+    #[clap(long, value_name = "RRGGBB", value_parser = parse_hex)]
+    mask: Option<Pixel>,
+
+    /// Maximum distance in 8 bit RGB space for a pixel to match the `--mask` color.
+    #[clap(long, value_name = "T", default_value_t = 32.0, requires = "mask")]
+    mask_tolerance: f32,
+
+    /// Soften staircase artifacts on diagonal edges in the color and greyscale styles.
+    #[clap(long)]
+    smooth: bool,
+
+    /// Maximum number of distinct colors per output line.
+    /// Similar colors are merged, trading fidelity for speed on slow terminals.
+    #[clap(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    max_colors_per_line: Option<u32>,
+
+    /// Exposure adjustment in stops, multiplying values by 2^STOPS.
+    /// Useful to reveal highlights or shadows of HDR images.
+    #[clap(long, value_name = "STOPS", allow_hyphen_values = true)]
+    exposure: Option<f32>,
+
+    /// Value added to every channel, from -1 to 1, e.g. 0.2 to make dark images legible.
+    #[clap(
+        long,
+        value_name = "OFFSET",
+        allow_hyphen_values = true,
+        value_parser = parse_brightness
+    )]
+    brightness: Option<f32>,
+
+    /// Factor to scale contrast around middle grey by, 1 leaves the image as it is.
+    #[clap(long, value_name = "FACTOR", value_parser = parse_factor)]
+    contrast: Option<f32>,
+
+    /// Factor to scale saturation by, 0 for greyscale and 1 to leave the image as it is.
+    #[clap(long, value_name = "FACTOR", value_parser = parse_factor)]
+    saturation: Option<f32>,
+
+    /// Gamma curve to apply, above 1 to brighten the midtones of dark photos and below 1
+    /// to darken them.
+    #[clap(long, default_value_t = 1.0, value_parser = parse_gamma)]
+    gamma: f32,
+
+    /// Tone map HDR images, compressing highlights above white instead of clipping them.
+    /// Applied after `--exposure`.
+    #[clap(long)]
+    tonemap: bool,
+
+    /// Brightness range to stretch to the full display range, given as `MIN,MAX`.
+    /// Values outside the window are clamped, revealing detail in narrow intensity bands.
+    #[clap(long, value_name = "MIN,MAX", allow_hyphen_values = true)]
+    window: Option<Window>,
+
+    /// Show the outlines of the image instead of the image itself, as the magnitude of a
+    /// Sobel filter that any style can render. Pairs well with braille and `--invert`.
+    #[clap(long)]
+    edges: bool,
+
+    /// Strength from 0 to 1 relative to the strongest edge above which outlines become white
+    /// and below black, instead of showing the magnitude of edges.
+    #[clap(long, value_name = "T", value_parser = parse_threshold, requires = "edges")]
+    edge_threshold: Option<f32>,
+
+    /// Invert the image, e.g. to show images meant for light terminals on a dark one.
+    /// Braille and gradient styles then draw the other pixels.
+    #[clap(long)]
+    invert: bool,
+
+    /// Invert greyscale and braille renders that would be hard to read on the terminal's background.
+    #[clap(long)]
+    auto_contrast: bool,
+
+    /// Terminal background to assume for `--auto-contrast` instead of detecting it.
+    #[clap(long)]
+    theme: Option<Theme>,
+
+    /// Show escape sequences as visible text, e.g. `\x1b[0m`, to inspect the exact output.
+    #[clap(long)]
+    debug_escapes: bool,
+
+    /// Show this many images side by side, with only the first frame of animations.
+    #[clap(
+        long,
+        value_name = "N",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..),
+        conflicts_with_all = ["protocol", "asciinema", "output"]
+    )]
+    columns: u32,
+
+    /// Show images as thumbnails in a grid of COLS x ROWS filling the terminal, with their
+    /// file names below them. More images continue in further rows.
+    #[clap(
+        long,
+        value_name = "COLSxROWS",
+        conflicts_with_all = ["columns", "width", "height", "zoom", "fit", "protocol", "asciinema", "output"]
+    )]
+    montage: Option<Grid>,
+
+    /// Number of blank lines between images and rows of images, and of spaces between
+    /// images shown side by side. One line and two spaces unless given.
+    #[clap(long, value_name = "N")]
+    gap: Option<usize>,
+
+    /// Write the rendered cells with their glyphs and colors as JSON for other tools instead
+    /// of escape sequences. Animations only show their first frame.
+    #[clap(
+        long,
+        value_enum,
+        default_value_t,
+        conflicts_with_all = ["protocol", "asciinema", "output", "columns", "montage", "debug_escapes"]
+    )]
+    format: OutputFormat,
+
+    /// Write an asciicast v2 recording to stdout instead of displaying the images.
+    /// Each file is shown as a slide for two seconds, animations for their length.
+    #[clap(long)]
+    asciinema: bool,
+
+    /// Print the format, dimensions, color type and file size of each image below its
+    /// file name, along with the camera and date from EXIF data when present.
+    #[clap(long)]
+    info: bool,
+
+    /// Do not print the file name above each image.
+    #[clap(short, long, visible_alias = "no-header")]
+    quiet: bool,
+
+    /// Number of times to play animated images, 0 to loop until Ctrl-C.
+    /// Animations play forever in a terminal and only their first frame is shown otherwise,
+    /// unless this is given. Redirected to a file, every frame is written in turn without
+    /// waiting, so that `cat` replays the animation, with graphics protocols as well.
+    #[clap(long = "loop", value_name = "N")]
+    loop_count: Option<u32>,
+
+    /// Show only the first frame of animated images, even in a terminal.
+    #[clap(long, conflicts_with_all = ["loop_count", "fps"])]
+    still: bool,
+
+    /// Show only frame N of animated images, counting from 0.
+    #[clap(long, value_name = "N", conflicts_with_all = ["still", "loop_count", "fps"])]
+    frame: Option<usize>,
+
+    /// Highest number of animation frames to show per second, skipping frames in between
+    /// for slow terminals. Frames the terminal cannot keep up with are always skipped.
+    #[clap(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    fps: Option<u32>,
+
+    /// Show photos as stored instead of rotating and flipping them upright according to
+    /// their EXIF orientation.
+    #[clap(long)]
+    no_auto_orient: bool,
+
+    /// Save the resized image to this file, the format is chosen by its extension.
+    #[clap(long, value_name = "FILE")]
+    save: Option<PathBuf>,
+
+    /// Draw the rendered text into an image file instead of printing it, with 8x16 pixel cells
+    /// and white on black for default colors. The format is chosen by the file extension.
+    #[clap(long, value_name = "FILE", conflicts_with_all = ["protocol", "asciinema"])]
+    output: Option<PathBuf>,
+
+    /// Write the raw 8 bit RGB (or RGBA for images with transparency) bytes of the resized image
+    /// to this file, `-` for stdout. Writing to stdout replaces the terminal output.
+    #[clap(long, value_name = "FILE")]
+    raw_out: Option<PathBuf>,
+
+    /// Write a JSON summary of every processed image to this file.
+    #[clap(long, value_name = "FILE")]
+    report_json: Option<PathBuf>,
+
+    /// Ignore the config file.
+    #[clap(long)]
+    no_config: bool,
+
     /// Print version info.
     #[clap(short, long)]
     version: bool,
@@ -51,6 +683,12 @@ struct Args {
 enum Error {
     IO(std::io::Error),
     Decode(image::ImageError),
+    Encode(image::ImageError),
+    RawSize { expected: usize, actual: usize },
+    Empty,
+    Crop { crop: Crop, size: (u32, u32) },
+    Download(String),
+    Frame { index: usize, count: usize },
 }
 
 impl Display for Error {
@@ -58,53 +696,1066 @@ impl Display for Error {
         match self {
             Self::IO(err) => write!(f, "{err}"),
             Self::Decode(err) => write!(f, "{err}"),
+            Self::Encode(err) => write!(f, "{err}"),
+            Self::RawSize { expected, actual } => write!(
+                f,
+                "expected {expected} bytes of raw pixel data, got {actual}"
+            ),
+            Self::Empty => write!(f, "no image data"),
+            Self::Crop {
+                crop,
+                size: (width, height),
+            } => write!(f, "crop {crop} lies outside the {width}x{height} image"),
+            Self::Download(err) => write!(f, "{err}"),
+            Self::Frame { index, count } => {
+                write!(
+                    f,
+                    "frame {index} is out of range, the last is {}",
+                    count - 1
+                )
+            }
         }
     }
 }
 
 impl std::error::Error for Error {}
 
-fn build_display_string(image: &DynamicImage, style: &Style) -> String {
-    let mut image = image.to_rgb32f();
-    style.apply(&mut image)
+/// Cell height in pixels assumed for graphics protocols when the terminal does not report it.
+const DEFAULT_CELL_HEIGHT: u32 = 20;
+
+impl Args {
+    /// Size of a terminal cell in pixels when drawing with a graphics protocol.
+    fn cell_pixels(&self) -> Option<(u32, u32)> {
+        if !self.protocol.is_graphics() {
+            return None;
+        }
+        let detected = if self.reproducible {
+            None
+        } else {
+            terminal::cell_pixels()
+        };
+        detected.or_else(|| {
+            let width = (DEFAULT_CELL_HEIGHT as f32 * self.cell_aspect).round() as u32;
+            Some((width.max(1), DEFAULT_CELL_HEIGHT))
+        })
+    }
+
+    /// Crop, rotate and flip the image as requested, the same for every frame of animations.
+    fn transform(&self, image: DynamicImage) -> DynamicImage {
+        let image = match self.crop {
+            Some(crop) => crop.apply(&image),
+            None => image,
+        };
+        let image = match self.rotate {
+            Some(Rotation::Quarter) => image.rotate90(),
+            Some(Rotation::Half) => image.rotate180(),
+            Some(Rotation::ThreeQuarters) => image.rotate270(),
+            None => image,
+        };
+        match self.flip {
+            Some(Flip::Horizontal) => image.fliph(),
+            Some(Flip::Vertical) => image.flipv(),
+            None => image,
+        }
+    }
+
+    /// Size of the terminal window in pixels when filling it with `--fullscreen-pixels`.
+    fn window_pixels(&self) -> Option<(u32, u32)> {
+        if !self.fullscreen_pixels || !self.protocol.is_graphics() || self.reproducible {
+            return None;
+        }
+        terminal::window_pixels()
+    }
+
+    /// Whether only one frame of animations is shown, as asked with `--still` or `--frame`.
+    fn single_frame(&self) -> bool {
+        self.still || self.frame.is_some()
+    }
+
+    /// Whether animations are played with their timing and cursor movements: in a terminal
+    /// or when a number of loops is given, but neither for reproducible output nor when
+    /// asked for a single frame. Recordings are timed by the frame delays alone instead.
+    fn plays_animations(&self) -> bool {
+        !self.reproducible
+            && !self.single_frame()
+            && (self.loop_count.is_some() || std::io::stdout().is_terminal())
+    }
+
+    /// Blank lines written after each image or row of images.
+    fn line_gap(&self) -> usize {
+        self.gap.unwrap_or(1)
+    }
+
+    /// Spaces between images shown side by side.
+    fn column_gap(&self) -> usize {
+        self.gap.unwrap_or(2)
+    }
+
+    /// Multiplexer graphics have to pass through, unless disabled or reproducible.
+    fn multiplexer(&self) -> Option<Multiplexer> {
+        if self.no_tmux_passthrough || self.reproducible {
+            return None;
+        }
+        detect_multiplexer(|name| std::env::var(name).ok())
+    }
+
+    /// Width to height ratio of a rendered pixel.
+    fn pixel_aspect(&self) -> f32 {
+        match self.cell_pixels() {
+            Some(_) => 1.0,
+            None => self.style.pixel_aspect(self.cell_aspect),
+        }
+    }
+
+    /// Image size in pixels that fills `cells` as `(columns, rows)`.
+    fn pixel_count(&self, (columns, rows): (u32, u32)) -> (u32, u32) {
+        match self.cell_pixels() {
+            Some((width, height)) => (columns * width, rows * height),
+            None => self.style.pixel_count((columns, rows)),
+        }
+    }
+
+    /// Number of terminal `(columns, rows)` an image of `(width, height)` pixels occupies.
+    fn cell_count(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        match self.cell_pixels() {
+            Some((cell_width, cell_height)) => {
+                (width.div_ceil(cell_width), height.div_ceil(cell_height))
+            }
+            None => self.style.cell_count((width, height)),
+        }
+    }
+}
+
+/// Terminal rows taken by the filename header and the prompt after the image.
+const RESERVED_ROWS: u32 = 2;
+
+/// Size of a montage cell as `(columns, rows)`, the screen divided by the grid with room
+/// for gaps and labels. Without a known height, cells are about square.
+fn montage_cell(args: &Args, grid: Grid) -> (u32, u32) {
+    let (columns, rows) = screen_size(args).unwrap_or((80, None));
+    let gaps = args.column_gap() as u32 * (grid.columns - 1);
+    let width = (columns.saturating_sub(gaps) / grid.columns).max(1);
+    let height = match rows {
+        Some(rows) => {
+            // Every row of the grid also takes its labels and a blank line.
+            let extra = if args.quiet { 1 } else { 2 };
+            let rows = rows.saturating_sub(RESERVED_ROWS) / grid.rows;
+            rows.saturating_sub(extra)
+        }
+        None => (width as f32 * args.cell_aspect).round() as u32,
+    };
+    (width, height.max(1))
+}
+
+/// Size of the screen as `(columns, rows)`, queried from the terminal or else read from
+/// `COLUMNS` and `LINES`. Reproducible output does not depend on either.
+fn screen_size(args: &Args) -> Option<(u32, Option<u32>)> {
+    if args.reproducible {
+        return None;
+    }
+    terminal_size()
+        .map(|(columns, rows)| (columns, Some(rows)))
+        .or_else(|| size_from_env(|name| std::env::var(name).ok()))
 }
 
-fn load_image(path: impl AsRef<Path>) -> Result<DynamicImage, Error> {
-    ImageReader::open(path)
-        .map_err(Error::IO)?
-        .decode()
-        .map_err(Error::Decode)
+/// Dimensions to resize an image of `original` size to, following the command line and
+/// scaled down to the maximum width unless forced. Reproducible output is only limited by
+/// `--max-width`, not by the terminal.
+fn target_size(args: &Args, original: (u32, u32)) -> (Option<u32>, Option<u32>) {
+    let dim = requested_size(args, original);
+    if args.force || args.window_pixels().is_some() {
+        return dim;
+    }
+    let terminal_width = || {
+        terminal_size()
+            .filter(|_| !args.reproducible)
+            .map(|(columns, _)| columns)
+    };
+    let Some(max_columns) = args.max_width.or_else(terminal_width) else {
+        return dim;
+    };
+    let max_width = args.pixel_count((max_columns, 1)).0.max(1);
+    let (width, height) = target_dimensions(original, dim, args.pixel_aspect());
+    if width <= max_width {
+        return dim;
+    }
+    eprintln!("warning: limiting width to {max_columns} columns, use --force to render wider");
+    let height = (height as u64 * max_width as u64 / width as u64).max(1) as u32;
+    (Some(max_width), Some(height))
+}
+
+/// Dimensions asked for on the command line, or fitting the terminal without any.
+fn requested_size(args: &Args, original: (u32, u32)) -> (Option<u32>, Option<u32>) {
+    if let Some(grid) = args.montage {
+        let bounds = args.pixel_count(montage_cell(args, grid));
+        return fit(original, bounds, args.pixel_aspect());
+    }
+    if let Some(factor) = args.zoom {
+        return zoom(original, factor);
+    }
+    if let Some(window) = args.window_pixels() {
+        return fill(original, window, args.fit, 1.0);
+    }
+    if args.width.is_some() || args.height.is_some() {
+        if args.protocol.is_graphics() {
+            let (width, height) =
+                args.pixel_count((args.width.unwrap_or(0), args.height.unwrap_or(0)));
+            return (args.width.map(|_| width), args.height.map(|_| height));
+        }
+        return (args.width, args.height);
+    }
+    // Keep the exact pixel grid of codes unless a size was requested.
+    if matches!(args.style, Style::Mono) && args.fit.is_none() {
+        return zoom(original, 1.0);
+    }
+    match screen_size(args) {
+        Some((columns, None)) => (Some(args.pixel_count((columns, 1)).0), None),
+        Some((columns, Some(rows))) => {
+            let rows = rows.saturating_sub(RESERVED_ROWS).max(1);
+            let bounds = args.pixel_count((columns, rows));
+            fill(original, bounds, args.fit, args.pixel_aspect())
+        }
+        None => (Some(80), None),
+    }
+}
+
+/// Dimensions to resize an image of `original` size to for `bounds` pixels of the screen,
+/// fitting within them unless `fit` stretches or crops the image to fill them.
+fn fill(
+    original: (u32, u32),
+    bounds: (u32, u32),
+    fit_mode: Option<Fit>,
+    pixel_aspect: f32,
+) -> (Option<u32>, Option<u32>) {
+    match fit_mode {
+        Some(Fit::Stretch | Fit::Cover) => (Some(bounds.0), Some(bounds.1)),
+        None | Some(Fit::Contain) => fit(original, bounds, pixel_aspect),
+    }
+}
+
+/// Despeckle, pixelate and resize the image to `dim` as requested on the command line.
+fn scale(image: DynamicImage, dim: (Option<u32>, Option<u32>), args: &Args) -> DynamicImage {
+    let pixel_aspect = args.pixel_aspect();
+    let image = if args.despeckle {
+        despeckle(&image)
+    } else {
+        image
+    };
+    let image = match args.pixelate {
+        Some(size) => pixelate(&image, size),
+        None => image,
+    };
+    // Fixed boxes are stretched unless asked to keep the aspect ratio.
+    let (dim, bounds) = match (dim, args.fit) {
+        ((Some(width), Some(height)), Some(Fit::Contain)) => {
+            let bounds = (width, height);
+            (fit(image.dimensions(), bounds, pixel_aspect), Some(bounds))
+        }
+        ((Some(width), Some(height)), Some(Fit::Cover)) => {
+            let bounds = (width, height);
+            (
+                cover(image.dimensions(), bounds, pixel_aspect),
+                Some(bounds),
+            )
+        }
+        _ => (dim, None),
+    };
+    let image = if args.sample_point {
+        sample_point(&image, dim, pixel_aspect)
+    } else {
+        let filter = if args.pixel_art {
+            Filter::Nearest
+        } else {
+            args.filter.unwrap_or_default()
+        };
+        resize(image, dim, filter, pixel_aspect)
+    };
+    match bounds {
+        Some(bounds) => center(&image, bounds),
+        None => image,
+    }
+}
+
+/// Write rendered images side by side, with their file names above them on stderr.
+fn write_row(
+    row: &[(String, String)],
+    args: &Args,
+    writer: &mut impl Write,
+) -> std::io::Result<()> {
+    let outputs: Vec<&str> = row.iter().map(|(_, output)| output.as_str()).collect();
+    if let Some(grid) = args.montage {
+        // Cells of a montage line up across rows, with labels below that fit them.
+        let (width, _) = montage_cell(args, grid);
+        let widths = vec![width as usize; row.len()];
+        write!(
+            writer,
+            "{}",
+            side_by_side(&outputs, &widths, args.column_gap())
+        )?;
+        if !args.quiet {
+            let labels: Vec<String> = row
+                .iter()
+                .map(|(name, _)| label(name, width as usize))
+                .collect();
+            let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+            write!(
+                writer,
+                "{}",
+                side_by_side(&labels, &widths, args.column_gap())
+            )?;
+        }
+        write!(writer, "{}", "\n".repeat(args.line_gap()))?;
+        return writer.flush();
+    }
+    let names: Vec<String> = row.iter().map(|(name, _)| format!("{name}:")).collect();
+    let widths: Vec<usize> = outputs
+        .iter()
+        .zip(&names)
+        .map(|(output, name)| {
+            let width = block_width(output);
+            if args.quiet {
+                width
+            } else {
+                width.max(visible_width(name))
+            }
+        })
+        .collect();
+    if !args.quiet {
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        eprint!("{}", side_by_side(&names, &widths, args.column_gap()));
+    }
+    write!(
+        writer,
+        "{}",
+        side_by_side(&outputs, &widths, args.column_gap())
+    )?;
+    write!(writer, "{}", "\n".repeat(args.line_gap()))?;
+    writer.flush()
+}
+
+/// File name of `path` cut to at most `width` columns, ending in `…` when cut.
+fn label(path: &str, width: usize) -> String {
+    let name = Path::new(path)
+        .file_name()
+        .map_or(path.into(), |name| name.to_string_lossy());
+    if visible_width(&name) <= width {
+        return name.into_owned();
+    }
+    let mut label = String::new();
+    let mut used = 0;
+    for c in name.chars() {
+        used += visible_width(c.encode_utf8(&mut [0; 4]));
+        if used > width.saturating_sub(1) {
+            break;
+        }
+        label.push(c);
+    }
+    label.push('…');
+    label
+}
+
+fn build_display_string(image: &DynamicImage, args: &Args) -> String {
+    let mut output = Vec::new();
+    display(image, args, &mut output).expect("writing to memory cannot fail");
+    String::from_utf8(output).expect("rendered output is UTF-8")
+}
+
+/// Pixel rows of text output rendered and flushed at a time, so that large images
+/// appear progressively instead of all at once.
+const STRIP_HEIGHT: u32 = 96;
+
+/// Render the image according to `args` and write it to `writer` as it is produced.
+fn display(image: &DynamicImage, args: &Args, writer: &mut impl Write) -> std::io::Result<()> {
+    let style = &args.style;
+    let options = RenderOptions {
+        alpha: alpha_mask(image, args),
+        ..render_options(args)
+    };
+    let mut image = prepare(image, args);
+    if args.protocol.is_graphics() {
+        let output = args.protocol.encode(
+            &image,
+            options.alpha.as_ref(),
+            args.cell_count(image.dimensions()),
+        );
+        if args.multiplexer() == Some(Multiplexer::Tmux) {
+            return writer.write_all(tmux_passthrough(&output).as_bytes());
+        }
+        return writer.write_all(output.as_bytes());
+    }
+    #[cfg(feature = "json")]
+    if args.format == OutputFormat::Json {
+        return style.apply_json(&mut image, &options, writer);
+    }
+    style.apply_in_strips(&mut image, &options, STRIP_HEIGHT, writer)?;
+    if args.show_palette && style.uses_palette() {
+        let strip = palette_strip(&image, style.cell_count(image.dimensions()).0, args.colors);
+        writer.write_all(strip.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Draw the text the image is rendered as into pixels, as a terminal would show it.
+fn rasterize(image: &DynamicImage, args: &Args) -> RgbImage {
+    let options = RenderOptions {
+        alpha: alpha_mask(image, args),
+        ..render_options(args)
+    };
+    let mut image = prepare(image, args);
+    args.style.rasterize(&mut image, &options)
+}
+
+/// Apply the background and adjustments from `args` to the resized image.
+fn prepare(image: &DynamicImage, args: &Args) -> Rgb32FImage {
+    let style = &args.style;
+    // Transparent and masked pixels need a background to show, default to black, unless
+    // graphics protocols draw them transparent.
+    let transparent = image.color().has_alpha() || args.mask.is_some();
+    let flatten = !args.protocol.is_graphics() || args.flatten;
+    let background = args
+        .tile_background
+        .clone()
+        .or_else(|| args.background.map(Background::Solid))
+        .or_else(|| (transparent && flatten).then(|| Background::Solid(Pixel::from([0.0; 3]))));
+    let mut image = match background {
+        Some(background) => {
+            let mut image = image.to_rgba32f();
+            if let Some(key) = args.mask {
+                mask(&mut image, key, args.mask_tolerance);
+            }
+            composite(&image, &background)
+        }
+        None => image.to_rgb32f(),
+    };
+    if let Some(stops) = args.exposure {
+        exposure(&mut image, stops);
+    }
+    if args.tonemap {
+        tonemap(&mut image);
+    }
+    if let Some(range) = args.window {
+        window(&mut image, range);
+    }
+    if let Some(offset) = args.brightness {
+        brightness(&mut image, offset);
+    }
+    if let Some(factor) = args.contrast {
+        contrast(&mut image, factor);
+    }
+    if let Some(factor) = args.saturation {
+        saturation(&mut image, factor);
+    }
+    if args.gamma != 1.0 {
+        gamma(&mut image, args.gamma);
+    }
+    if args.edges {
+        image = sobel(&image, &render_options(args));
+        // Even strong edges are faint after downscaling, the strongest one is shown as white.
+        normalize(&mut image);
+        if let Some(threshold) = args.edge_threshold {
+            binarize(&mut image, threshold);
+        }
+    }
+    if args.invert {
+        invert(&mut image);
+    }
+    if args.auto_contrast {
+        let theme = match args.theme {
+            Some(theme) => Some(theme),
+            None if args.reproducible => None,
+            None => detect_theme(),
+        };
+        if let (Some(theme), Some(assumed)) = (theme, style.assumed_theme())
+            && theme != assumed
+        {
+            invert(&mut image);
+        }
+    }
+    // Graphics protocols show the pixels as they are.
+    if args.protocol.is_graphics() {
+        return image;
+    }
+    if args.smooth && matches!(style, Style::Color | Style::Greyscale) {
+        smooth(&mut image);
+    }
+    if let Some(max_colors) = args.max_colors_per_line {
+        let (_, line_height) = style.cell_size();
+        limit_colors_per_line(&mut image, line_height, max_colors as usize);
+    }
+    image
+}
+
+fn render_options(args: &Args) -> RenderOptions {
+    RenderOptions {
+        braille_gradient: args.braille_gradient,
+        threshold: args.threshold,
+        dither: args.dither,
+        serpentine: args.serpentine,
+        seed: args.seed,
+        colors: args.colors,
+        linear: !args.gamma_brightness,
+        luma: args.luma.unwrap_or(if args.gamma_brightness {
+            Luma::Rec601
+        } else {
+            Luma::Rec709
+        }),
+        background: args.background.unwrap_or(Pixel::from([0.0; 3])),
+        glyph: args.glyph,
+        alpha: None,
+    }
+}
+
+/// Alpha channel of the resized image for graphics protocols and styles that leave out
+/// transparent pixels, unless a background to composite them onto was chosen.
+fn alpha_mask(image: &DynamicImage, args: &Args) -> Option<GrayImage> {
+    let transparent = image.color().has_alpha() || args.mask.is_some();
+    let background = args.background.is_some() || args.tile_background.is_some();
+    let uses_alpha = if args.protocol.is_graphics() {
+        !args.flatten
+    } else {
+        args.style.uses_alpha()
+    };
+    if !transparent || background || !uses_alpha {
+        return None;
+    }
+    let mut image = image.to_rgba32f();
+    if let Some(key) = args.mask {
+        mask(&mut image, key, args.mask_tolerance);
+    }
+    Some(GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        image::Luma([to_u8(image.get_pixel(x, y)[3])])
+    }))
+}
+
+/// Decoded image along with what is known about its source.
+struct Loaded {
+    image: DynamicImage,
+    format: Option<ImageFormat>,
+    /// Size of the encoded image in bytes.
+    file_size: u64,
+    /// Raw EXIF block for formats that carry one.
+    exif: Option<Vec<u8>>,
+}
+
+fn load_image(path: impl AsRef<Path>, auto_orient: bool) -> Result<Loaded, Error> {
+    let file_size = std::fs::metadata(&path).map_err(Error::IO)?.len();
+    let reader = ImageReader::open(path).map_err(Error::IO)?;
+    let format = reader.format();
+    let (image, exif) = decode(reader, auto_orient)?;
+    Ok(Loaded {
+        image,
+        format,
+        file_size,
+        exif,
+    })
+}
+
+/// Replace the image with frame `index` of an animation, counting from 0.
+/// Still images, standard input and URLs only have frame 0.
+fn select_frame(loaded: Loaded, path: &str, index: usize) -> Result<Loaded, Error> {
+    let frames = if path == "-" || fetch::is_url(path) {
+        None
+    } else {
+        animation::frames(Path::new(path), loaded.format).map_err(Error::Decode)?
+    };
+    match frames {
+        Some(mut frames) if index < frames.len() => Ok(Loaded {
+            image: frames.swap_remove(index).0,
+            ..loaded
+        }),
+        None if index == 0 => Ok(loaded),
+        frames => Err(Error::Frame {
+            index,
+            count: frames.map_or(1, |frames| frames.len()),
+        }),
+    }
+}
+
+/// Decode the image along with its EXIF block and, when `auto_orient` is set, rotate and
+/// flip it upright as its EXIF orientation says. Unreadable orientations leave the image
+/// as stored.
+fn decode(
+    reader: ImageReader<impl BufRead + Seek>,
+    auto_orient: bool,
+) -> Result<(DynamicImage, Option<Vec<u8>>), Error> {
+    let mut decoder = reader.into_decoder().map_err(Error::Decode)?;
+    let exif = decoder.exif_metadata().ok().flatten();
+    let orientation = if auto_orient {
+        decoder.orientation().unwrap_or(Orientation::NoTransforms)
+    } else {
+        Orientation::NoTransforms
+    };
+    let mut image = DynamicImage::from_decoder(decoder).map_err(Error::Decode)?;
+    image.apply_orientation(orientation);
+    Ok((image, exif))
+}
+
+/// Read a whole image from `reader`, as encoded by `format`.
+fn load_reader(
+    mut reader: impl Read,
+    format: StdinFormat,
+    size: Option<Size>,
+    channels: u8,
+    auto_orient: bool,
+) -> Result<Loaded, Error> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).map_err(Error::IO)?;
+    if bytes.is_empty() {
+        return Err(Error::Empty);
+    }
+    let file_size = bytes.len() as u64;
+    match format {
+        StdinFormat::Encoded => {
+            let reader = ImageReader::new(Cursor::new(&bytes))
+                .with_guessed_format()
+                .map_err(Error::IO)?;
+            let format = reader.format();
+            let (image, exif) = decode(reader, auto_orient)?;
+            Ok(Loaded {
+                image,
+                format,
+                file_size,
+                exif,
+            })
+        }
+        StdinFormat::Raw => {
+            let Size { width, height } = size.expect("raw stdin format requires a size");
+            let expected = width as usize * height as usize * channels as usize;
+            let size_error = Error::RawSize {
+                expected,
+                actual: bytes.len(),
+            };
+            if bytes.len() != expected {
+                return Err(size_error);
+            }
+            let image = match channels {
+                3 => RgbImage::from_raw(width, height, bytes).map(DynamicImage::ImageRgb8),
+                _ => RgbaImage::from_raw(width, height, bytes).map(DynamicImage::ImageRgba8),
+            };
+            Ok(Loaded {
+                image: image.ok_or(size_error)?,
+                format: None,
+                file_size,
+                exif: None,
+            })
+        }
+    }
+}
+
+/// Seconds each file is shown for in recordings.
+const SLIDE_DURATION: f64 = 2.0;
+
+/// 8 bit version of the image, keeping the alpha channel only if there is one.
+fn to_8bit(image: &DynamicImage) -> DynamicImage {
+    if image.color().has_alpha() {
+        DynamicImage::ImageRgba8(image.to_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(image.to_rgb8())
+    }
+}
+
+fn save_image(image: &DynamicImage, path: &Path) -> Result<(), Error> {
+    // The PNM encoder defaults to PAM, but `.ppm` files are expected to be binary pixmaps.
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ppm"))
+    {
+        let file = File::create(path).map_err(Error::IO)?;
+        let encoder =
+            PnmEncoder::new(file).with_subtype(PnmSubtype::Pixmap(SampleEncoding::Binary));
+        return image
+            .to_rgb8()
+            .write_with_encoder(encoder)
+            .map_err(Error::Encode);
+    }
+    to_8bit(image).save(path).map_err(Error::Encode)
+}
+
+fn write_raw(image: &DynamicImage, path: &Path) -> Result<(), Error> {
+    let image = to_8bit(image);
+    if path == Path::new("-") {
+        std::io::stdout()
+            .lock()
+            .write_all(image.as_bytes())
+            .map_err(Error::IO)
+    } else {
+        std::fs::write(path, image.as_bytes()).map_err(Error::IO)
+    }
 }
 
 pub mod built_info {
     include!(concat!(env!("OUT_DIR"), "/built.rs"));
 }
 
-fn main() {
-    let mut args = Args::parse();
+fn main() -> ExitCode {
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    // Config files depend on the machine, so reproducible output ignores them.
+    if !args.no_config
+        && !args.reproducible
+        && let Some(path) = config::default_path()
+    {
+        let loaded = Config::load(&path).and_then(|config| match config {
+            Some(config) => config.apply(&mut args, &matches),
+            None => Ok(()),
+        });
+        if let Err(err) = loaded {
+            eprintln!("{}: {err}", path.display());
+        }
+    }
+    if args.format == OutputFormat::Json && !cfg!(feature = "json") {
+        eprintln!("--format json needs ttview built with the `json` feature");
+        return ExitCode::FAILURE;
+    }
     if args.version {
         let version = built_info::PKG_VERSION;
         println!("ttview {}", version);
     }
-    if let Some(gradient) = args.gradient {
+    if let Some(preset) = args.gradient_preset {
+        args.gradient = Some(preset.characters().to_string());
+    }
+    if let Some(gradient) = &args.gradient {
         let gradient = gradient.chars().collect();
         args.style = Style::Gradient(gradient);
     }
-    let style = args.style;
-    let filter = args.filter.unwrap_or_default();
-    let dim = match (args.width, args.height) {
-        (None, None) => (Some(80), None),
-        other => other,
-    };
-    for filename in &args.filenames {
-        let image = match load_image(filename) {
-            Ok(img) => img,
+    if let Some(colors) = &args.color_gradient {
+        args.style = Style::ColorGradient(colors.clone());
+    }
+    // See https://no-color.org, reproducible output cannot depend on the environment.
+    // A style given on the command line is taken as a request for its colors.
+    let explicit_style = matches.value_source("style") == Some(ValueSource::CommandLine);
+    let no_color_env = !args.reproducible
+        && !explicit_style
+        && std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    if args.no_color || no_color_env {
+        if !matches!(args.style, Style::Gradient(_)) {
+            args.style = Style::Gradient(ASCII_GRADIENT.chars().collect());
+        }
+        args.protocol = Protocol::Text;
+    }
+    if let Style::Auto = args.style {
+        // Reproducible output cannot depend on the terminal, so it assumes the default style.
+        let capability = if args.reproducible {
+            Capability::TrueColor
+        } else {
+            detect_capability(|name| std::env::var(name).ok())
+        };
+        args.style = match capability {
+            Capability::Text => Style::Gradient(ASCII_GRADIENT.chars().collect()),
+            _ => Style::Color,
+        };
+        if capability == Capability::Ansi256 {
+            args.colors = ColorDepth::Ansi256;
+        }
+        // Only text has cells to write as JSON.
+        if !args.protocol.is_graphics()
+            && args.output.is_none()
+            && args.format == OutputFormat::Text
+        {
+            args.protocol = match capability {
+                Capability::Kitty => Protocol::Kitty,
+                Capability::Sixel => Protocol::Sixel,
+                _ => Protocol::Text,
+            };
+        }
+    }
+    if args.protocol.is_graphics() && args.multiplexer() == Some(Multiplexer::Screen) {
+        eprintln!("warning: screen does not pass graphics through, try tmux or --protocol text");
+    }
+    if matches!(args.style, Style::Sextant) && lacks_sextants(|name| std::env::var(name).ok()) {
+        eprintln!("warning: the terminal font may lack sextant characters, try --style quadrant");
+    }
+    // Later files are still shown after a failure, which only affects the exit code.
+    let mut failed = false;
+    // Files with the settings given after their name, shared by everything a name expands to.
+    let mut files = Vec::new();
+    for argument in &args.filenames {
+        let (path, overrides) = match overrides::split(argument) {
+            Ok(split) => split,
+            // Query strings of URLs can look like settings.
+            Err(_) if fetch::is_url(argument) => (argument.as_str(), None),
+            Err(err) => {
+                eprintln!("{err}");
+                failed = true;
+                continue;
+            }
+        };
+        if fetch::is_url(path) {
+            files.push((path.to_string(), overrides));
+            continue;
+        }
+        let mut filenames = Vec::new();
+        if let Err(err) = expand::expand(path, args.recursive, &mut filenames) {
+            eprintln!("{path}: {err}");
+            failed = true;
+        }
+        files.extend(filenames.into_iter().map(|path| (path, overrides.clone())));
+    }
+    let single = args.save.is_some() || args.raw_out.is_some() || args.output.is_some();
+    if single && files.len() > 1 {
+        eprintln!("--save, --raw-out and --output can only be used with a single file");
+        return ExitCode::FAILURE;
+    }
+    if let Some(Command::Bench { filename }) = &args.command {
+        return bench::run(filename, &args);
+    }
+    // Image output and side by side images only show the first frame.
+    let columns = args.montage.map_or(args.columns, |grid| grid.columns);
+    let animate = args.output.is_none()
+        && args.format == OutputFormat::Text
+        && columns == 1
+        && !args.single_frame()
+        && (args.asciinema || args.plays_animations());
+    let mut time = 0.0;
+    // Still images are written as they are rendered and flushed once complete.
+    let mut stdout = BufWriter::new(std::io::stdout().lock());
+    let mut recording = Recording::default();
+    let mut report = Vec::new();
+    // Images waiting to be shown side by side, with their file names.
+    let mut row = Vec::new();
+    for (path, overrides) in &files {
+        let file_args = overrides.as_ref().map(|overrides| overrides.applied(&args));
+        let args = file_args.as_ref().unwrap_or(&args);
+        let (filename, loaded) = if path == "-" {
+            let loaded = load_reader(
+                std::io::stdin().lock(),
+                args.stdin_format,
+                args.raw_size,
+                args.raw_channels,
+                !args.no_auto_orient,
+            );
+            ("<stdin>", loaded)
+        } else if fetch::is_url(path) {
+            let loaded = fetch::download(path, args.timeout)
+                .map_err(Error::Download)
+                .and_then(|bytes| {
+                    let encoded = StdinFormat::Encoded;
+                    load_reader(bytes.as_slice(), encoded, None, 3, !args.no_auto_orient)
+                });
+            (path.as_str(), loaded)
+        } else {
+            (path.as_str(), load_image(path, !args.no_auto_orient))
+        };
+        let loaded = loaded.and_then(|loaded| match args.frame {
+            Some(index) => select_frame(loaded, path, index),
+            None => Ok(loaded),
+        });
+        let loaded = loaded.and_then(|loaded| match args.crop {
+            Some(crop) if !crop.fits(loaded.image.dimensions()) => Err(Error::Crop {
+                crop,
+                size: loaded.image.dimensions(),
+            }),
+            _ => Ok(loaded),
+        });
+        let Loaded {
+            image,
+            format,
+            file_size,
+            exif,
+        } = match loaded {
+            Ok(loaded) => loaded,
             Err(err) => {
-                println!("{filename}: {err}");
+                report.push(report::Entry {
+                    path: path.clone(),
+                    result: Err(err.to_string()),
+                });
+                eprintln!("{filename}: {err}");
+                failed = true;
+                continue;
+            }
+        };
+        let original = image.dimensions();
+        let info = args
+            .info
+            .then(|| info::describe(format, original, image.color(), file_size, exif.as_deref()));
+        let image = args.transform(image);
+        let transformed = image.dimensions();
+        // Animations are fit again when the terminal is resized.
+        let fitted = || {
+            let dim = target_size(args, transformed);
+            if args.pixel_art {
+                integer_scale(transformed, dim, args.pixel_aspect())
+            } else {
+                dim
+            }
+        };
+        let image = scale(image, fitted(), args);
+        if let Some(path) = &args.save
+            && let Err(err) = save_image(&image, path)
+        {
+            eprintln!("{}: {err}", path.display());
+            failed = true;
+        }
+        if let Some(path) = &args.raw_out {
+            if let Err(err) = write_raw(&image, path) {
+                eprintln!("{}: {err}", path.display());
+                failed = true;
+            }
+            if path == Path::new("-") {
+                continue;
+            }
+        }
+        let frames = if animate && path != "-" && !fetch::is_url(path) {
+            animation::frames(Path::new(path), format).unwrap_or_else(|err| {
+                eprintln!("{filename}: {err}");
+                None
+            })
+        } else {
+            None
+        };
+        let render = |image: &DynamicImage| {
+            let output = build_display_string(image, args);
+            if args.debug_escapes {
+                output.replace('\x1B', "\\x1b")
+            } else {
+                output
+            }
+        };
+        let mut header = if args.quiet {
+            String::new()
+        } else {
+            format!("{filename}:\n")
+        };
+        if let Some(info) = &info {
+            header += info;
+        }
+        let rendered = args.cell_count(image.dimensions());
+        report.push(report::Entry {
+            path: path.clone(),
+            result: Ok(report::Details {
+                format,
+                original,
+                rendered,
+                style: args.style.to_string(),
+            }),
+        });
+        if let Some(path) = &args.output {
+            let raster = DynamicImage::ImageRgb8(rasterize(&image, args));
+            if let Err(err) = save_image(&raster, path) {
+                eprintln!("{}: {err}", path.display());
+                failed = true;
+            }
+            continue;
+        }
+        let Some(frames) = frames else {
+            if args.asciinema {
+                let output = render(&image);
+                let size = (rendered.0, rendered.1 + header.lines().count() as u32);
+                recording.push(time, &format!("\x1B[H\x1B[2J{header}{output}"), size);
+                time += SLIDE_DURATION;
+                continue;
+            }
+            if columns > 1 || args.montage.is_some() {
+                // File names are shown above the row, so details are listed as files load.
+                if let Some(info) = &info {
+                    eprint!("{filename}:\n{info}");
+                }
+                row.push((filename.to_string(), render(&image)));
+                if row.len() == columns as usize {
+                    let written = write_row(&row, args, &mut stdout);
+                    row.clear();
+                    if let Err(err) = written {
+                        eprintln!("{err}");
+                        failed = true;
+                        break;
+                    }
+                }
                 continue;
             }
+            // The header goes to stderr so that redirected output only holds the image.
+            eprint!("{header}");
+            let gap = "\n".repeat(args.line_gap());
+            let written = if args.debug_escapes {
+                write!(stdout, "{}{gap}", render(&image))
+            } else {
+                display(&image, args, &mut stdout).and_then(|()| write!(stdout, "{gap}"))
+            };
+            if let Err(err) = written.and_then(|()| stdout.flush()) {
+                eprintln!("{err}");
+                failed = true;
+                break;
+            }
+            continue;
+        };
+        // Frames are drawn over each other without a header so they line up.
+        let sources: Vec<_> = frames
+            .into_iter()
+            .map(|(frame, delay)| (args.transform(frame), delay))
+            .collect();
+        let render_frames = || {
+            let dim = fitted();
+            sources
+                .iter()
+                .map(|(frame, _)| render(&scale(frame.clone(), dim, args)))
+                .collect::<Vec<_>>()
+        };
+        let delays = sources.iter().map(|(_, delay)| *delay);
+        let frames: Vec<_> = render_frames().into_iter().zip(delays).collect();
+        if args.asciinema {
+            // Recordings cannot loop forever, so they play the animation once unless asked.
+            let loops = args.loop_count.unwrap_or(1).max(1);
+            recording.push(time, "\x1B[H\x1B[2J", rendered);
+            for (output, delay) in frames.iter().cycle().take(frames.len() * loops as usize) {
+                recording.push(time, &format!("\x1B[H{output}"), rendered);
+                time += delay.as_secs_f64();
+            }
+        } else {
+            // Reproducible output does not depend on the terminal size.
+            let mut watch = SizeWatch::new(|| terminal_size().filter(|_| !args.reproducible));
+            let refit = || watch.changed().map(|_| render_frames());
+            let loops = args.loop_count.unwrap_or(0);
+            if let Err(err) = animation::play(frames, loops, args.fps, refit) {
+                eprintln!("{err}");
+            }
+            if animation::interrupted() {
+                break;
+            }
+        }
+    }
+    if !row.is_empty()
+        && let Err(err) = write_row(&row, &args, &mut stdout)
+    {
+        eprintln!("{err}");
+        failed = true;
+    }
+    if let Some(path) = &args.report_json
+        && let Err(err) = File::create(path).and_then(|file| report::write(&report, file))
+    {
+        eprintln!("{}: {err}", path.display());
+        failed = true;
+    }
+    if args.asciinema
+        && let Err(err) = recording.write(&mut stdout)
+    {
+        eprintln!("{err}");
+        failed = true;
+    }
+    if let Err(err) = stdout.flush() {
+        eprintln!("{err}");
+        failed = true;
+    }
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fullscreen_fills_the_reported_window() {
+        // A 1920x1080 window as reported by `CSI 14 t`.
+        let window = (1920, 1080);
+        let transmitted = |original, fit_mode| {
+            let dim = fill(original, window, fit_mode, 1.0);
+            target_dimensions(original, dim, 1.0)
         };
-        let image = resize(image, dim, filter);
-        println!("{filename}:\n{}", build_display_string(&image, &style));
+        assert_eq!(transmitted((400, 200), None), (1920, 960));
+        assert_eq!(transmitted((100, 400), None), (270, 1080));
+        assert_eq!(transmitted((400, 200), Some(Fit::Stretch)), window);
+        assert_eq!(transmitted((400, 200), Some(Fit::Cover)), window);
     }
 }
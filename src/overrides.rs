@@ -0,0 +1,84 @@
+use crate::Args;
+use crate::resizing::Filter;
+use crate::styling::Style;
+use clap::ValueEnum;
+use std::path::Path;
+
+/// Settings for a single file, given after its name as `path:key=value,...`.
+#[derive(Debug, Default, Clone)]
+pub struct Overrides {
+    style: Option<Style>,
+    gradient: Option<String>,
+    filter: Option<Filter>,
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+const KEYS: &str = "style, gradient, filter, width, height";
+
+/// Split a filename argument into its path and overrides. Arguments that name an existing
+/// file or have no `key=value` after their last colon are paths as a whole.
+pub fn split(argument: &str) -> Result<(&str, Option<Overrides>), String> {
+    let Some((path, spec)) = argument.rsplit_once(':') else {
+        return Ok((argument, None));
+    };
+    if !spec.contains('=') || Path::new(argument).exists() {
+        return Ok((argument, None));
+    }
+    let mut overrides = Overrides::default();
+    for setting in spec.split(',') {
+        let (key, value) = setting
+            .split_once('=')
+            .ok_or_else(|| format!("expected key=value in '{argument}', got '{setting}'"))?;
+        let invalid =
+            |expected: &str| format!("invalid {key} '{value}' in '{argument}', {expected}");
+        let size = || match value.parse::<u32>() {
+            Ok(size) if size > 0 => Ok(size),
+            _ => Err(invalid("expected a positive number")),
+        };
+        match key {
+            "style" => {
+                let style = Style::from_str(value, true).map_err(|_| invalid("see --help"))?;
+                if let Style::Auto = style {
+                    return Err(invalid("auto can only be given with --style"));
+                }
+                overrides.style = Some(style);
+            }
+            "gradient" => overrides.gradient = Some(crate::parse_gradient(value)?),
+            "filter" => {
+                overrides.filter =
+                    Some(Filter::from_str(value, true).map_err(|_| invalid("see --help"))?)
+            }
+            "width" => overrides.width = Some(size()?),
+            "height" => overrides.height = Some(size()?),
+            _ => {
+                return Err(format!(
+                    "unknown setting '{key}' in '{argument}', expected one of {KEYS}"
+                ));
+            }
+        }
+    }
+    Ok((path, Some(overrides)))
+}
+
+impl Overrides {
+    /// Copy of `args` with these settings in place of the global ones.
+    pub fn applied(&self, args: &Args) -> Args {
+        let mut args = args.clone();
+        if let Some(gradient) = &self.gradient {
+            args.style = Style::Gradient(gradient.chars().collect());
+        } else if let Some(style) = &self.style {
+            args.style = style.clone();
+        }
+        if self.filter.is_some() {
+            args.filter = self.filter;
+        }
+        // Like on the command line, a single dimension keeps the aspect ratio.
+        if self.width.is_some() || self.height.is_some() {
+            args.width = self.width;
+            args.height = self.height;
+            args.zoom = None;
+        }
+        args
+    }
+}
@@ -0,0 +1,196 @@
+//! Draw rendered cells back into pixels, showing output as a terminal would display it.
+
+use crate::Pixel;
+use crate::colors::to_u8;
+use crate::styling::{Cell, QUADRANTS};
+use image::{Rgb, RgbImage};
+
+/// Width of a cell in pixels.
+pub const CELL_WIDTH: u32 = 8;
+/// Height of a cell in pixels.
+pub const CELL_HEIGHT: u32 = 16;
+
+/// Colors used for cells without their own, as on a dark terminal.
+const DEFAULT_FG: Rgb<u8> = Rgb([255, 255, 255]);
+const DEFAULT_BG: Rgb<u8> = Rgb([0, 0, 0]);
+
+/// Draw lines of cells, each taking `CELL_WIDTH` by `CELL_HEIGHT` pixels.
+pub(crate) fn draw(lines: &[Vec<Cell>]) -> RgbImage {
+    let columns = lines.iter().map(Vec::len).max().unwrap_or(0) as u32;
+    let mut canvas = RgbImage::from_pixel(
+        columns * CELL_WIDTH,
+        lines.len() as u32 * CELL_HEIGHT,
+        DEFAULT_BG,
+    );
+    for (row, line) in lines.iter().enumerate() {
+        for (column, cell) in line.iter().enumerate() {
+            let fg = cell.fg.map_or(DEFAULT_FG, to_rgb8);
+            let bg = cell.bg.map_or(DEFAULT_BG, to_rgb8);
+            for y in 0..CELL_HEIGHT {
+                for x in 0..CELL_WIDTH {
+                    let color = if covers(cell.glyph, x, y) { fg } else { bg };
+                    canvas.put_pixel(
+                        column as u32 * CELL_WIDTH + x,
+                        row as u32 * CELL_HEIGHT + y,
+                        color,
+                    );
+                }
+            }
+        }
+    }
+    canvas
+}
+
+fn to_rgb8(color: Pixel) -> Rgb<u8> {
+    Rgb(color.0.map(to_u8))
+}
+
+/// Whether the glyph covers pixel `(x, y)` of its cell and shows the foreground color there.
+/// Block elements, shades and braille are drawn exactly, printable ASCII with an 8x8 font
+/// stretched to the cell height and anything else as `?`.
+fn covers(glyph: char, x: u32, y: u32) -> bool {
+    let code = glyph as u32;
+    // Block elements fill the parts of an even grid whose bits are set, in row major order.
+    let grid = |columns: u32, rows: u32, mask: u32| {
+        let bit = y * rows / CELL_HEIGHT * columns + x * columns / CELL_WIDTH;
+        mask >> bit & 1 == 1
+    };
+    match glyph {
+        _ if let Some(mask) = QUADRANTS.iter().position(|&quadrant| quadrant == glyph) => {
+            grid(2, 2, mask as u32)
+        }
+        '▔' => y < CELL_HEIGHT / 8,
+        '\u{2581}'..='\u{2588}' => y >= CELL_HEIGHT - (code - 0x2580) * CELL_HEIGHT / 8,
+        '░' => x.is_multiple_of(2) && y.is_multiple_of(2),
+        '▒' => (x + y).is_multiple_of(2),
+        '▓' => x.is_multiple_of(2) || y.is_multiple_of(2),
+        '\u{1FB00}'..='\u{1FB3B}' => grid(2, 3, sextant_mask(code - 0x1FB00)),
+        '\u{2800}'..='\u{28FF}' => {
+            // Dots are numbered down the left column, then the right, with the bottom row last.
+            let (column, row) = (x * 2 / CELL_WIDTH, y * 4 / CELL_HEIGHT);
+            let bit = if row == 3 {
+                6 + column
+            } else {
+                column * 3 + row
+            };
+            // Each dot fills the middle of its 4x4 pixel part of the cell.
+            let (dot_x, dot_y) = (x % (CELL_WIDTH / 2), y % (CELL_HEIGHT / 4));
+            let dot = (1..3).contains(&dot_x) && (1..3).contains(&dot_y);
+            dot && code >> bit & 1 == 1
+        }
+        ' '..='~' => FONT[(code - 0x20) as usize][(y * 8 / CELL_HEIGHT) as usize] >> x & 1 == 1,
+        _ => covers('?', x, y),
+    }
+}
+
+/// Pixels filled by the sextant at `offset` in its block, the inverse of `styling::sextant`.
+fn sextant_mask(offset: u32) -> u32 {
+    let mut mask = offset + 1;
+    if mask >= 0b010101 {
+        mask += 1;
+    }
+    if mask >= 0b101010 {
+        mask += 1;
+    }
+    mask
+}
+
+/// Printable ASCII glyphs from `' '` to `'~'`, one byte per row with bit 0 as the left pixel.
+/// From the public domain font8x8 by Daniel Hepper, based on the IBM PC BIOS font.
+const FONT: [[u8; 8]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    [0x18, 0x3C, 0x3C, 0x18, 0x18, 0x00, 0x18, 0x00],
+    [0x36, 0x36, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    [0x36, 0x36, 0x7F, 0x36, 0x7F, 0x36, 0x36, 0x00],
+    [0x0C, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x0C, 0x00],
+    [0x00, 0x63, 0x33, 0x18, 0x0C, 0x66, 0x63, 0x00],
+    [0x1C, 0x36, 0x1C, 0x6E, 0x3B, 0x33, 0x6E, 0x00],
+    [0x06, 0x06, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00],
+    [0x18, 0x0C, 0x06, 0x06, 0x06, 0x0C, 0x18, 0x00],
+    [0x06, 0x0C, 0x18, 0x18, 0x18, 0x0C, 0x06, 0x00],
+    [0x00, 0x66, 0x3C, 0xFF, 0x3C, 0x66, 0x00, 0x00],
+    [0x00, 0x0C, 0x0C, 0x3F, 0x0C, 0x0C, 0x00, 0x00],
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x06],
+    [0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x00],
+    [0x60, 0x30, 0x18, 0x0C, 0x06, 0x03, 0x01, 0x00],
+    [0x3E, 0x63, 0x73, 0x7B, 0x6F, 0x67, 0x3E, 0x00],
+    [0x0C, 0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x3F, 0x00],
+    [0x1E, 0x33, 0x30, 0x1C, 0x06, 0x33, 0x3F, 0x00],
+    [0x1E, 0x33, 0x30, 0x1C, 0x30, 0x33, 0x1E, 0x00],
+    [0x38, 0x3C, 0x36, 0x33, 0x7F, 0x30, 0x78, 0x00],
+    [0x3F, 0x03, 0x1F, 0x30, 0x30, 0x33, 0x1E, 0x00],
+    [0x1C, 0x06, 0x03, 0x1F, 0x33, 0x33, 0x1E, 0x00],
+    [0x3F, 0x33, 0x30, 0x18, 0x0C, 0x0C, 0x0C, 0x00],
+    [0x1E, 0x33, 0x33, 0x1E, 0x33, 0x33, 0x1E, 0x00],
+    [0x1E, 0x33, 0x33, 0x3E, 0x30, 0x18, 0x0E, 0x00],
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x00],
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x06],
+    [0x18, 0x0C, 0x06, 0x03, 0x06, 0x0C, 0x18, 0x00],
+    [0x00, 0x00, 0x3F, 0x00, 0x00, 0x3F, 0x00, 0x00],
+    [0x06, 0x0C, 0x18, 0x30, 0x18, 0x0C, 0x06, 0x00],
+    [0x1E, 0x33, 0x30, 0x18, 0x0C, 0x00, 0x0C, 0x00],
+    [0x3E, 0x63, 0x7B, 0x7B, 0x7B, 0x03, 0x1E, 0x00],
+    [0x0C, 0x1E, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x00],
+    [0x3F, 0x66, 0x66, 0x3E, 0x66, 0x66, 0x3F, 0x00],
+    [0x3C, 0x66, 0x03, 0x03, 0x03, 0x66, 0x3C, 0x00],
+    [0x1F, 0x36, 0x66, 0x66, 0x66, 0x36, 0x1F, 0x00],
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x46, 0x7F, 0x00],
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x06, 0x0F, 0x00],
+    [0x3C, 0x66, 0x03, 0x03, 0x73, 0x66, 0x7C, 0x00],
+    [0x33, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x33, 0x00],
+    [0x1E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00],
+    [0x78, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E, 0x00],
+    [0x67, 0x66, 0x36, 0x1E, 0x36, 0x66, 0x67, 0x00],
+    [0x0F, 0x06, 0x06, 0x06, 0x46, 0x66, 0x7F, 0x00],
+    [0x63, 0x77, 0x7F, 0x7F, 0x6B, 0x63, 0x63, 0x00],
+    [0x63, 0x67, 0x6F, 0x7B, 0x73, 0x63, 0x63, 0x00],
+    [0x1C, 0x36, 0x63, 0x63, 0x63, 0x36, 0x1C, 0x00],
+    [0x3F, 0x66, 0x66, 0x3E, 0x06, 0x06, 0x0F, 0x00],
+    [0x1E, 0x33, 0x33, 0x33, 0x3B, 0x1E, 0x38, 0x00],
+    [0x3F, 0x66, 0x66, 0x3E, 0x36, 0x66, 0x67, 0x00],
+    [0x1E, 0x33, 0x07, 0x0E, 0x38, 0x33, 0x1E, 0x00],
+    [0x3F, 0x2D, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00],
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x3F, 0x00],
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00],
+    [0x63, 0x63, 0x63, 0x6B, 0x7F, 0x77, 0x63, 0x00],
+    [0x63, 0x63, 0x36, 0x1C, 0x1C, 0x36, 0x63, 0x00],
+    [0x33, 0x33, 0x33, 0x1E, 0x0C, 0x0C, 0x1E, 0x00],
+    [0x7F, 0x63, 0x31, 0x18, 0x4C, 0x66, 0x7F, 0x00],
+    [0x1E, 0x06, 0x06, 0x06, 0x06, 0x06, 0x1E, 0x00],
+    [0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x40, 0x00],
+    [0x1E, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1E, 0x00],
+    [0x08, 0x1C, 0x36, 0x63, 0x00, 0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF],
+    [0x0C, 0x0C, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00],
+    [0x07, 0x06, 0x06, 0x3E, 0x66, 0x66, 0x3B, 0x00],
+    [0x00, 0x00, 0x1E, 0x33, 0x03, 0x33, 0x1E, 0x00],
+    [0x38, 0x30, 0x30, 0x3E, 0x33, 0x33, 0x6E, 0x00],
+    [0x00, 0x00, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00],
+    [0x1C, 0x36, 0x06, 0x0F, 0x06, 0x06, 0x0F, 0x00],
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x1F],
+    [0x07, 0x06, 0x36, 0x6E, 0x66, 0x66, 0x67, 0x00],
+    [0x0C, 0x00, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00],
+    [0x30, 0x00, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E],
+    [0x07, 0x06, 0x66, 0x36, 0x1E, 0x36, 0x67, 0x00],
+    [0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00],
+    [0x00, 0x00, 0x33, 0x7F, 0x7F, 0x6B, 0x63, 0x00],
+    [0x00, 0x00, 0x1F, 0x33, 0x33, 0x33, 0x33, 0x00],
+    [0x00, 0x00, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00],
+    [0x00, 0x00, 0x3B, 0x66, 0x66, 0x3E, 0x06, 0x0F],
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x78],
+    [0x00, 0x00, 0x3B, 0x6E, 0x66, 0x06, 0x0F, 0x00],
+    [0x00, 0x00, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x00],
+    [0x08, 0x0C, 0x3E, 0x0C, 0x0C, 0x2C, 0x18, 0x00],
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00],
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00],
+    [0x00, 0x00, 0x63, 0x6B, 0x7F, 0x7F, 0x36, 0x00],
+    [0x00, 0x00, 0x63, 0x36, 0x1C, 0x36, 0x63, 0x00],
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x3E, 0x30, 0x1F],
+    [0x00, 0x00, 0x3F, 0x19, 0x0C, 0x26, 0x3F, 0x00],
+    [0x38, 0x0C, 0x0C, 0x07, 0x0C, 0x0C, 0x38, 0x00],
+    [0x18, 0x18, 0x18, 0x00, 0x18, 0x18, 0x18, 0x00],
+    [0x07, 0x0C, 0x0C, 0x38, 0x0C, 0x0C, 0x07, 0x00],
+    [0x6E, 0x3B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+];
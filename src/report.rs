@@ -0,0 +1,120 @@
+use image::ImageFormat;
+use std::io::Write;
+use ttview::json;
+
+/// Outcome of processing a single image.
+#[derive(Debug)]
+pub struct Entry {
+    pub path: String,
+    pub result: Result<Details, String>,
+}
+
+/// Details about a successfully rendered image.
+#[derive(Debug)]
+pub struct Details {
+    pub format: Option<ImageFormat>,
+    /// Source image size in pixels.
+    pub original: (u32, u32),
+    /// Rendered size in terminal columns and rows.
+    pub rendered: (u32, u32),
+    pub style: String,
+}
+
+/// Write all entries as a JSON array of objects.
+pub fn write(entries: &[Entry], mut writer: impl Write) -> std::io::Result<()> {
+    writeln!(writer, "[")?;
+    for (i, entry) in entries.iter().enumerate() {
+        write!(writer, "  {{\"path\": {}, ", json::string(&entry.path))?;
+        match &entry.result {
+            Ok(details) => {
+                let format = match details.format {
+                    Some(format) => json::string(&format!("{format:?}").to_lowercase()),
+                    None => "null".to_string(),
+                };
+                write!(
+                    writer,
+                    "\"success\": true, \"format\": {format}, \
+                     \"original\": {{\"width\": {}, \"height\": {}}}, \
+                     \"rendered\": {{\"columns\": {}, \"rows\": {}}}, \"style\": {}}}",
+                    details.original.0,
+                    details.original.1,
+                    details.rendered.0,
+                    details.rendered.1,
+                    json::string(&details.style),
+                )?;
+            }
+            Err(error) => {
+                write!(
+                    writer,
+                    "\"success\": false, \"error\": {}}}",
+                    json::string(error)
+                )?;
+            }
+        }
+        let separator = if i + 1 < entries.len() { "," } else { "" };
+        writeln!(writer, "{separator}")?;
+    }
+    writeln!(writer, "]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failures_and_successes() {
+        let entries = [
+            Entry {
+                path: "missing.png".to_string(),
+                result: Err("No such file or directory (os error 2)".to_string()),
+            },
+            Entry {
+                path: "photos\\\"best\"\tof.png".to_string(),
+                result: Ok(Details {
+                    format: Some(ImageFormat::Png),
+                    original: (640, 480),
+                    rendered: (80, 30),
+                    style: "color".to_string(),
+                }),
+            },
+            Entry {
+                path: "-".to_string(),
+                result: Ok(Details {
+                    format: None,
+                    original: (2, 2),
+                    rendered: (2, 1),
+                    style: "braille".to_string(),
+                }),
+            },
+        ];
+        let mut output = Vec::new();
+        write(&entries, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "[",
+                r#"  {"path": "missing.png", "success": false, "error": "No such file or directory (os error 2)"},"#,
+                concat!(
+                    r#"  {"path": "photos\\\"best\"\tof.png", "success": true, "format": "png", "#,
+                    r#""original": {"width": 640, "height": 480}, "#,
+                    r#""rendered": {"columns": 80, "rows": 30}, "style": "color"},"#
+                ),
+                concat!(
+                    r#"  {"path": "-", "success": true, "format": null, "#,
+                    r#""original": {"width": 2, "height": 2}, "#,
+                    r#""rendered": {"columns": 2, "rows": 1}, "style": "braille"}"#
+                ),
+                "]",
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_array() {
+        let mut output = Vec::new();
+        write(&[], &mut output).unwrap();
+        assert_eq!(output, b"[\n]\n");
+    }
+}
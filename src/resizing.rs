@@ -1,7 +1,9 @@
 use image::imageops::FilterType;
-use image::{DynamicImage, GenericImageView};
+use image::{DynamicImage, GenericImageView, Rgba, Rgba32FImage};
 
-#[derive(Debug, Default, Copy, Clone, clap::ValueEnum)]
+#[derive(Debug, Default, Copy, Clone)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum, serde::Deserialize))]
+#[cfg_attr(feature = "cli", serde(rename_all = "kebab-case"))]
 pub enum Filter {
     /// Nearest Neighbor
     Nearest,
@@ -18,33 +20,261 @@ pub enum Filter {
 
     /// Lanczos with window 3
     Lanczos3,
+
+    /// Mitchell-Netravali cubic, less ringing than Lanczos and sharper than Gaussian
+    Mitchell,
+}
+
+/// How an image is scaled into a box of fixed width and height.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum, serde::Deserialize))]
+#[cfg_attr(feature = "cli", serde(rename_all = "kebab-case"))]
+pub enum Fit {
+    /// Scale to exactly the box, ignoring the aspect ratio.
+    #[default]
+    Stretch,
+
+    /// Scale to fit within the box and center the image, leaving transparent borders.
+    Contain,
+
+    /// Scale to fill the box and crop what sticks out around the center.
+    Cover,
 }
 
+/// Resize the image to `dim`, deriving a missing dimension from the aspect ratio.
+/// `pixel_aspect` is the width to height ratio of a rendered pixel in the terminal.
 pub fn resize(
     image: DynamicImage,
     dim: (Option<u32>, Option<u32>),
     filter: Filter,
+    pixel_aspect: f32,
 ) -> DynamicImage {
+    let (width, height) = target_dimensions(image.dimensions(), dim, pixel_aspect);
     let filter = match filter {
         Filter::Nearest => FilterType::Nearest,
         Filter::Triangle => FilterType::Triangle,
         Filter::CatmullRom => FilterType::CatmullRom,
         Filter::Gaussian => FilterType::Gaussian,
         Filter::Lanczos3 => FilterType::Lanczos3,
+        Filter::Mitchell => return mitchell(&image, width, height),
     };
+    image.resize_exact(width, height, filter)
+}
+
+/// Dimensions to pass to `resize` to scale an image of `image_dims` by `factor`.
+pub fn zoom((width, height): (u32, u32), factor: f32) -> (Option<u32>, Option<u32>) {
+    let scale = |size: u32| ((size as f32 * factor).round() as u32).max(1);
+    (Some(scale(width)), Some(scale(height)))
+}
+
+/// Dimensions to pass to `resize` so that an image of `image_dims` fits within `bounds`
+/// pixels, preserving its aspect ratio as corrected by `pixel_aspect`.
+pub fn fit(
+    image_dims: (u32, u32),
+    (max_width, max_height): (u32, u32),
+    pixel_aspect: f32,
+) -> (Option<u32>, Option<u32>) {
+    let (_, height) = target_dimensions(image_dims, (Some(max_width), None), pixel_aspect);
+    if height <= max_height {
+        (Some(max_width), None)
+    } else {
+        (None, Some(max_height.max(1)))
+    }
+}
+
+/// Dimensions to pass to `resize` so that an image of `image_dims` covers all of `bounds`
+/// pixels, preserving its aspect ratio as corrected by `pixel_aspect`.
+pub fn cover(
+    image_dims: (u32, u32),
+    (min_width, min_height): (u32, u32),
+    pixel_aspect: f32,
+) -> (Option<u32>, Option<u32>) {
+    let (_, height) = target_dimensions(image_dims, (Some(min_width), None), pixel_aspect);
+    if height >= min_height {
+        (Some(min_width), None)
+    } else {
+        (None, Some(min_height))
+    }
+}
+
+/// Dimensions to pass to `resize` to scale an image of `image_dims` by the largest whole
+/// factor that keeps it within `dim`, so that every source pixel becomes the same number
+/// of rendered pixels. Images that do not fit even once are left at `dim`.
+pub fn integer_scale(
+    image_dims: (u32, u32),
+    dim: (Option<u32>, Option<u32>),
+    pixel_aspect: f32,
+) -> (Option<u32>, Option<u32>) {
+    let (width, _) = target_dimensions(image_dims, dim, pixel_aspect);
+    let factor = width / image_dims.0;
+    if factor == 0 {
+        return dim;
+    }
+    let width = image_dims.0 * factor;
+    let (_, height) = target_dimensions(image_dims, (Some(width), None), pixel_aspect);
+    (Some(width), Some(height.max(1)))
+}
+
+/// Center the image in a box of `width` x `height` pixels, cropping it where it is larger
+/// and padding it with transparent pixels where it is smaller.
+pub fn center(image: &DynamicImage, (width, height): (u32, u32)) -> DynamicImage {
     let (img_width, img_height) = image.dimensions();
-    match dim {
+    let cropped = image.crop_imm(
+        img_width.saturating_sub(width) / 2,
+        img_height.saturating_sub(height) / 2,
+        width,
+        height,
+    );
+    if cropped.dimensions() == (width, height) {
+        return cropped;
+    }
+    let mut canvas = Rgba32FImage::new(width, height);
+    image::imageops::replace(
+        &mut canvas,
+        &cropped.to_rgba32f(),
+        (width.saturating_sub(cropped.width()) / 2) as i64,
+        (height.saturating_sub(cropped.height()) / 2) as i64,
+    );
+    DynamicImage::ImageRgba32F(canvas)
+}
+
+/// Point-sample the image on a grid of the target dimensions instead of filtering.
+/// Much cheaper than `resize` for tiny outputs, at the cost of aliasing.
+pub fn sample_point(
+    image: &DynamicImage,
+    dim: (Option<u32>, Option<u32>),
+    pixel_aspect: f32,
+) -> DynamicImage {
+    // Sampled at full precision, so that HDR and 16 bit values are kept.
+    let source = image.to_rgba32f();
+    let (img_width, img_height) = source.dimensions();
+    let (width, height) = target_dimensions((img_width, img_height), dim, pixel_aspect);
+    let sampled = Rgba32FImage::from_fn(width, height, |x, y| {
+        let source_x = ((x as f32 + 0.5) * img_width as f32 / width as f32) as u32;
+        let source_y = ((y as f32 + 0.5) * img_height as f32 / height as f32) as u32;
+        *source.get_pixel(source_x.min(img_width - 1), source_y.min(img_height - 1))
+    });
+    let sampled = DynamicImage::ImageRgba32F(sampled);
+    // Opaque images stay without alpha, so that they are not treated as transparent.
+    if image.color().has_alpha() {
+        sampled
+    } else {
+        DynamicImage::ImageRgb32F(sampled.to_rgb32f())
+    }
+}
+
+/// Replace every `size` x `size` block of the image with its average color,
+/// as if downscaling by `size` and scaling back up with nearest neighbor.
+pub fn pixelate(image: &DynamicImage, size: u32) -> DynamicImage {
+    let mut source = image.to_rgba32f();
+    let (width, height) = source.dimensions();
+    for top in (0..height).step_by(size as usize) {
+        for left in (0..width).step_by(size as usize) {
+            let xs = left..(left + size).min(width);
+            let ys = top..(top + size).min(height);
+            let mut sum = [0.0; 4];
+            for y in ys.clone() {
+                for x in xs.clone() {
+                    let pixel = source.get_pixel(x, y);
+                    (0..4).for_each(|c| sum[c] += pixel[c]);
+                }
+            }
+            let count = (xs.len() * ys.len()) as f32;
+            let average = Rgba(sum.map(|c| c / count));
+            for y in ys.clone() {
+                for x in xs.clone() {
+                    source.put_pixel(x, y, average);
+                }
+            }
+        }
+    }
+    DynamicImage::ImageRgba32F(source)
+}
+
+/// Compute the final image size from the requested dimensions, preserving aspect ratio
+/// when only one of them is given. The derived dimension is corrected for rendered pixels
+/// that are not square, `pixel_aspect` being their width to height ratio. Neither dimension
+/// is less than 1, so that very wide or tall images keep a row or column.
+pub fn target_dimensions(
+    (img_width, img_height): (u32, u32),
+    dim: (Option<u32>, Option<u32>),
+    pixel_aspect: f32,
+) -> (u32, u32) {
+    let (width, height) = match dim {
         (Some(width), None) => {
             let scale = (width as f32) / (img_width as f32);
-            let height = (img_height as f32 * scale) as u32;
-            image.resize(width, height, filter)
+            (width, (img_height as f32 * scale * pixel_aspect) as u32)
         }
         (None, Some(height)) => {
             let scale = (height as f32) / (img_height as f32);
-            let width = (img_width as f32 * scale) as u32;
-            image.resize(width, height, filter)
+            ((img_width as f32 * scale / pixel_aspect) as u32, height)
         }
-        (Some(width), Some(height)) => image.resize_exact(width, height, filter),
+        (Some(width), Some(height)) => (width, height),
         _ => unreachable!("impossible dimensions for resize!"),
+    };
+    (width.max(1), height.max(1))
+}
+
+/// Mitchell-Netravali kernel with B = C = 1/3.
+fn mitchell_kernel(x: f32) -> f32 {
+    const B: f32 = 1.0 / 3.0;
+    const C: f32 = 1.0 / 3.0;
+    let x = x.abs();
+    if x < 1.0 {
+        ((12.0 - 9.0 * B - 6.0 * C) * x.powi(3)
+            + (-18.0 + 12.0 * B + 6.0 * C) * x.powi(2)
+            + (6.0 - 2.0 * B))
+            / 6.0
+    } else if x < 2.0 {
+        ((-B - 6.0 * C) * x.powi(3)
+            + (6.0 * B + 30.0 * C) * x.powi(2)
+            + (-12.0 * B - 48.0 * C) * x
+            + (8.0 * B + 24.0 * C))
+            / 6.0
+    } else {
+        0.0
     }
 }
+
+/// Normalized kernel weights of the source samples contributing to each of `output` samples.
+fn mitchell_weights(input: u32, output: u32) -> Vec<Vec<(u32, f32)>> {
+    let ratio = input as f32 / output as f32;
+    // Widen the kernel when downscaling so every source sample contributes.
+    let scale = ratio.max(1.0);
+    let support = 2.0 * scale;
+    (0..output)
+        .map(|o| {
+            let center = (o as f32 + 0.5) * ratio - 0.5;
+            let first = (center - support).floor().max(0.0) as u32;
+            let last = ((center + support).ceil() as u32).min(input - 1);
+            let mut weights: Vec<(u32, f32)> = (first..=last)
+                .map(|i| (i, mitchell_kernel((i as f32 - center) / scale)))
+                .collect();
+            let sum: f32 = weights.iter().map(|(_, w)| w).sum();
+            weights.iter_mut().for_each(|(_, w)| *w /= sum);
+            weights
+        })
+        .collect()
+}
+
+/// Resize to exactly `width` x `height` using separable Mitchell-Netravali convolution.
+fn mitchell(image: &DynamicImage, width: u32, height: u32) -> DynamicImage {
+    let source = image.to_rgba32f();
+    let horizontal = mitchell_weights(source.width(), width);
+    let vertical = mitchell_weights(source.height(), height);
+    let convolve = |samples: &[(u32, f32)], pixel: &dyn Fn(u32) -> Rgba<f32>| {
+        let mut sum = [0.0; 4];
+        for &(i, weight) in samples {
+            let value = pixel(i);
+            (0..4).for_each(|c| sum[c] += value[c] * weight);
+        }
+        Rgba(sum)
+    };
+    let rows = Rgba32FImage::from_fn(width, source.height(), |x, y| {
+        convolve(&horizontal[x as usize], &|i| *source.get_pixel(i, y))
+    });
+    let result = Rgba32FImage::from_fn(width, height, |x, y| {
+        convolve(&vertical[y as usize], &|i| *rows.get_pixel(x, i))
+    });
+    DynamicImage::ImageRgba32F(result)
+}
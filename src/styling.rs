@@ -1,9 +1,18 @@
 use crate::Pixel;
+use crate::colors::{ColorDepth, Luma, to_linear, to_srgb, to_u8, xterm_index};
+use crate::dithering::{Dither, ordered_cube};
+use crate::raster;
+use crate::terminal::Theme;
 use image::Pixel as ImagePixel;
-use image::Rgb32FImage;
+use image::{GenericImage, GenericImageView, GrayImage, Rgb32FImage, RgbImage};
+use std::fmt::{Display, Formatter, Write};
+use std::io;
+use unicode_width::UnicodeWidthChar;
 
 /// Display style.
-#[derive(Debug, Default, Clone, clap::ValueEnum)]
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum, serde::Deserialize))]
+#[cfg_attr(feature = "cli", serde(rename_all = "kebab-case"))]
 pub enum Style {
     /// Default style, 24 bit color with upper half block character.
     #[default]
@@ -13,166 +22,1005 @@ pub enum Style {
     Greyscale,
 
     /// Display in greyscale using a gradient.
-    #[clap(skip)]
+    #[cfg_attr(feature = "cli", clap(skip), serde(skip))]
     Gradient(Vec<char>),
 
-    /// Braille style, using a threshold of 0.5 brightness.
+    /// False color, full blocks colored by interpolating between colors by brightness.
+    /// The first color is used for black, the last one for white.
+    #[cfg_attr(feature = "cli", clap(skip), serde(skip))]
+    ColorGradient(Vec<Pixel>),
+
+    /// Braille style, setting dots for pixels darker than the threshold.
     Braille,
 
-    /// Braille but with Floyd-Steinberg dithering.
+    /// Braille but with dithering, Floyd-Steinberg unless chosen otherwise.
     DitheredBraille,
 
     /// Dithered.
     Dithered,
+
+    /// Greyscale using eighth blocks for sub-cell vertical detail.
+    Subpixel,
+
+    /// Braille outlines of edges found with a Sobel filter.
+    BrailleEdges,
+
+    /// Color reduced to the 256 color cube with ordered Bayer dithering.
+    BayerColor,
+
+    /// Braille with the dots of bright pixels drawn in their average color.
+    ColorBraille,
+
+    /// Color with quadrant blocks, two colors per cell of 2x2 pixels.
+    Quadrant,
+
+    /// Color with Unicode 13 sextant blocks, two colors per cell of 2x3 pixels.
+    /// Needs a recent font.
+    Sextant,
+
+    /// Pure black and white with two full blocks per pixel, keeping square pixels for QR codes.
+    Mono,
+
+    /// Color with a single pixel per cell, drawn as a space on its color. Half the vertical
+    /// resolution of the color style, but the least demanding on the terminal and font.
+    /// Uses a full block in the pixel's color instead with `Glyph::FullBlock`.
+    Compact,
+
+    /// Best style and graphics protocol the terminal supports, see `terminal::Capability`.
+    /// The command line picks one for the terminal, elsewhere it renders like `Color`.
+    Auto,
+}
+
+/// Pixel rows that strips rendered by [`Style::apply_in_strips`] are a multiple of,
+/// divisible by the heights of all cells and ordered dither patterns.
+pub const STRIP_ALIGN: u32 = 48;
+
+/// Gradient used when no better style is supported, from dark to bright.
+pub const ASCII_GRADIENT: &str = " .:-=+*#%@";
+
+/// First character of a gradient that does not take exactly one terminal column, like wide
+/// CJK characters and emoji or zero width combining marks. The gradient style draws one
+/// character per cell, so these would break the grid of cells and the layout around it.
+pub fn non_single_width(gradient: &str) -> Option<char> {
+    gradient.chars().find(|c| c.width() != Some(1))
+}
+
+/// Named gradients for the gradient style.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum, serde::Deserialize))]
+#[cfg_attr(feature = "cli", serde(rename_all = "kebab-case"))]
+pub enum GradientPreset {
+    /// `" .:-=+*#%@"`, the ASCII gradient also used without color support.
+    Standard,
+
+    /// `" ░▒▓█"`, shade blocks.
+    Blocks,
+
+    /// `" .:#"`, few characters for a high contrast look.
+    Minimal,
+
+    /// The classic 70 character ASCII ramp for fine brightness steps.
+    Long,
+}
+
+impl GradientPreset {
+    /// Characters of the gradient, from dark to bright.
+    pub fn characters(&self) -> &'static str {
+        match self {
+            Self::Standard => ASCII_GRADIENT,
+            Self::Blocks => " ░▒▓█",
+            Self::Minimal => " .:#",
+            Self::Long => {
+                r#" .'`^",:;Il!i><~+_-?][}{1)(|\/tfjrxnuvczXYUJCLQ0OZmwqpdbkhao*#MW&8%B@$"#
+            }
+        }
+    }
+}
+
+/// Character the color style draws each cell of two pixels with.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Glyph {
+    /// `▀` in the color of the top pixel on the color of the bottom one.
+    #[default]
+    UpperHalf,
+
+    /// `▄` in the color of the bottom pixel on the color of the top one,
+    /// for terminals that draw the upper half block badly.
+    LowerHalf,
+
+    /// `█` in the average color of both pixels.
+    FullBlock,
+
+    /// A space on the average color of both pixels, which every terminal draws
+    /// without gaps, at half the vertical resolution.
+    SpaceBg,
+}
+
+/// Settings that fine-tune how styles render.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Light braille dots in an ordered pattern proportional to brightness
+    /// instead of using a single threshold.
+    pub braille_gradient: bool,
+
+    /// Brightness at which braille styles switch dots.
+    pub threshold: f32,
+
+    /// Algorithm used by the dithered styles.
+    pub dither: Dither,
+
+    /// Scan every other row right to left when diffusing errors.
+    pub serpentine: bool,
+
+    /// Seed of the texture for blue noise dithering.
+    pub seed: u64,
+
+    /// Colors used in escape sequences.
+    pub colors: ColorDepth,
+
+    /// Compute brightness from linear light instead of the gamma encoded values.
+    pub linear: bool,
+
+    /// Weights of the color channels in the brightness of a pixel.
+    /// Brightness from gamma encoded values used [`Luma::Rec601`] before this was configurable.
+    pub luma: Luma,
+
+    /// Color below the last row of pixels when half block styles render an odd height,
+    /// instead of leaving the lower half of those cells to the terminal.
+    pub background: Pixel,
+
+    /// Character the color style draws cells with.
+    pub glyph: Glyph,
+
+    /// Opacity of every pixel, braille styles leave out the dots of transparent ones so that
+    /// the terminal background shows through. Without it all pixels are opaque.
+    pub alpha: Option<GrayImage>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            braille_gradient: false,
+            threshold: 0.5,
+            colors: ColorDepth::default(),
+            linear: true,
+            luma: Luma::default(),
+            dither: Dither::default(),
+            serpentine: false,
+            seed: 0,
+            background: Pixel::from([0.0; 3]),
+            glyph: Glyph::default(),
+            alpha: None,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Perceived brightness of the pixel, gamma encoded so that 0.5 looks like middle grey.
+    fn brightness(&self, pixel: &Pixel) -> f32 {
+        let [r, g, b] = self.luma.weights();
+        if self.linear {
+            let [lr, lg, lb] = pixel.0.map(to_linear);
+            to_srgb(r * lr + g * lg + b * lb)
+        } else {
+            let [pr, pg, pb] = pixel.0;
+            r * pr + g * pg + b * pb
+        }
+    }
+
+    /// Average of two brightness values as if their light was mixed.
+    fn mix(&self, a: f32, b: f32) -> f32 {
+        if self.linear {
+            to_srgb((to_linear(a) + to_linear(b)) / 2.0)
+        } else {
+            (a + b) / 2.0
+        }
+    }
 }
 
 impl Style {
-    pub fn apply(&self, image: &mut Rgb32FImage) -> String {
-        let mut string = String::default();
+    /// Number of image pixels covered by a single terminal cell, as `(width, height)`.
+    pub fn cell_size(&self) -> (u32, u32) {
+        match self {
+            Self::Color
+            | Self::Greyscale
+            | Self::Gradient(_)
+            | Self::ColorGradient(_)
+            | Self::Dithered
+            | Self::Subpixel
+            | Self::BayerColor
+            | Self::Auto => (1, 2),
+            Self::Quadrant => (2, 2),
+            Self::Sextant => (2, 3),
+            Self::Braille | Self::DitheredBraille | Self::BrailleEdges | Self::ColorBraille => {
+                (2, 4)
+            }
+            Self::Mono | Self::Compact => (1, 1),
+        }
+    }
+
+    /// Width to height ratio of a single rendered pixel, given the ratio of a terminal cell.
+    pub fn pixel_aspect(&self, cell_aspect: f32) -> f32 {
+        if let Self::Mono = self {
+            // Every pixel spans two columns of a single row.
+            return 2.0 * cell_aspect;
+        }
+        let (cell_width, cell_height) = self.cell_size();
+        cell_aspect * cell_height as f32 / cell_width as f32
+    }
+
+    /// Terminal background the style is designed for, if it depends on one.
+    /// Greyscale styles draw bright pixels with more ink, braille draws dots for dark pixels.
+    pub fn assumed_theme(&self) -> Option<Theme> {
+        match self {
+            Self::Greyscale | Self::Gradient(_) | Self::Dithered | Self::Subpixel => {
+                Some(Theme::Dark)
+            }
+            Self::Braille | Self::DitheredBraille => Some(Theme::Light),
+            Self::Mono | Self::ColorBraille => Some(Theme::Dark),
+            Self::Color
+            | Self::ColorGradient(_)
+            | Self::BrailleEdges
+            | Self::BayerColor
+            | Self::Quadrant
+            | Self::Sextant
+            | Self::Compact
+            | Self::Auto => None,
+        }
+    }
+
+    /// Whether the style leaves out transparent pixels given in [`RenderOptions::alpha`].
+    pub fn uses_alpha(&self) -> bool {
+        matches!(
+            self,
+            Self::Braille | Self::DitheredBraille | Self::BrailleEdges | Self::ColorBraille
+        )
+    }
+
+    /// Whether the style reduces colors to a fixed palette.
+    pub fn uses_palette(&self) -> bool {
+        matches!(self, Self::BayerColor)
+    }
+
+    /// Number of terminal `(columns, rows)` needed to display an image of the given dimensions.
+    pub fn cell_count(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        if let Self::Mono = self {
+            return (width * 2, height);
+        }
+        let (cell_width, cell_height) = self.cell_size();
+        (width.div_ceil(cell_width), height.div_ceil(cell_height))
+    }
+
+    /// Image size in pixels that fills `columns` by `rows` cells, the inverse of `cell_count`.
+    pub fn pixel_count(&self, (columns, rows): (u32, u32)) -> (u32, u32) {
+        if let Self::Mono = self {
+            return (columns / 2, rows);
+        }
+        let (cell_width, cell_height) = self.cell_size();
+        (columns * cell_width, rows * cell_height)
+    }
+
+    /// Render the image and write it to `writer` one line at a time.
+    pub fn apply(
+        &self,
+        image: &mut Rgb32FImage,
+        options: &RenderOptions,
+        writer: &mut impl io::Write,
+    ) -> io::Result<()> {
+        encode(&self.cells(image, options), options.colors, writer)
+    }
+
+    /// Render the image in strips of about `strip_height` pixel rows, writing and flushing each
+    /// as soon as it is done, so that the top of large images shows before the rest is rendered.
+    /// The output is the same as from [`Style::apply`]. Strips are rounded up to whole multiples
+    /// of [`STRIP_ALIGN`] rows so that cells and ordered dither patterns line up across them.
+    /// Styles where pixels depend on distant ones, like error diffusion dithering and edge
+    /// detection, render the whole image before writing any of it.
+    pub fn apply_in_strips(
+        &self,
+        image: &mut Rgb32FImage,
+        options: &RenderOptions,
+        strip_height: u32,
+        writer: &mut impl io::Write,
+    ) -> io::Result<()> {
+        if self.needs_whole_image(options) {
+            self.apply(image, options, writer)?;
+            return writer.flush();
+        }
+        let strip_height = strip_height.max(1).next_multiple_of(STRIP_ALIGN);
+        let width = image.width();
+        for top in (0..image.height()).step_by(strip_height as usize) {
+            let height = strip_height.min(image.height() - top);
+            let mut strip = image.view(0, top, width, height).to_image();
+            let options = RenderOptions {
+                alpha: (options.alpha.as_ref())
+                    .map(|alpha| alpha.view(0, top, width, height).to_image()),
+                ..options.clone()
+            };
+            encode(&self.cells(&mut strip, &options), options.colors, writer)?;
+            writer.flush()?;
+            // Keep what rendering did to the pixels, as `apply` would.
+            image
+                .copy_from(&strip, 0, top)
+                .expect("strip fits where it was taken from");
+        }
+        Ok(())
+    }
+
+    /// Whether rendering any part of the image depends on pixels outside of the rows it covers.
+    pub fn needs_whole_image(&self, options: &RenderOptions) -> bool {
+        match self {
+            Self::Dithered | Self::DitheredBraille => options.dither.diffuses(),
+            Self::BrailleEdges => true,
+            _ => false,
+        }
+    }
+
+    /// Render the image and write its cells as JSON instead of escape sequences, for tools that
+    /// draw the output themselves. The output is an array of lines, each an array of cells as
+    /// `{"glyph": "▀", "fg": [255, 0, 0], "bg": null}`, with 8 bit colors or `null` for the
+    /// terminal's default color. Colors are not reduced to `options.colors`.
+    #[cfg(feature = "json")]
+    pub fn apply_json(
+        &self,
+        image: &mut Rgb32FImage,
+        options: &RenderOptions,
+        writer: &mut impl io::Write,
+    ) -> io::Result<()> {
+        let color = |color: Option<Pixel>| match color {
+            Some(color) => {
+                let [r, g, b] = color.0.map(to_u8);
+                format!("[{r}, {g}, {b}]")
+            }
+            None => "null".to_string(),
+        };
+        let lines = self.cells(image, options);
+        writeln!(writer, "[")?;
+        for (i, line) in lines.iter().enumerate() {
+            let cells: Vec<String> = line
+                .iter()
+                .map(|cell| {
+                    format!(
+                        "{{\"glyph\": {}, \"fg\": {}, \"bg\": {}}}",
+                        crate::json::string(&cell.glyph.to_string()),
+                        color(cell.fg),
+                        color(cell.bg)
+                    )
+                })
+                .collect();
+            let separator = if i + 1 < lines.len() { "," } else { "" };
+            writeln!(writer, "  [{}]{separator}", cells.join(", "))?;
+        }
+        write!(writer, "]")
+    }
+
+    /// Render the image into a string, see [`Style::apply`].
+    pub fn apply_to_string(&self, image: &mut Rgb32FImage, options: &RenderOptions) -> String {
+        let mut output = Vec::new();
+        self.apply(image, options, &mut output)
+            .expect("writing to memory cannot fail");
+        String::from_utf8(output).expect("encoded cells are UTF-8")
+    }
+
+    /// Draw the image as a terminal would show the rendered text, see [`raster`](crate::raster).
+    pub fn rasterize(&self, image: &mut Rgb32FImage, options: &RenderOptions) -> RgbImage {
+        raster::draw(&self.cells(image, options))
+    }
+
+    /// Compute the cells for each line of output.
+    fn cells(&self, image: &mut Rgb32FImage, options: &RenderOptions) -> Vec<Vec<Cell>> {
+        let mut lines = Vec::new();
         match self {
-            Self::Color => {
+            Self::Color | Self::Auto => {
                 for y in (0..image.height()).step_by(2) {
+                    let mut line = Vec::new();
                     for x in 0..image.width() {
-                        string += &fg(image.get_pixel(x, y));
-                        if let Some(bot) = image.get_pixel_checked(x, y + 1) {
-                            string += &bg(bot);
-                        }
-                        string += "▀\x1B[0m";
+                        let top = *image.get_pixel(x, y);
+                        let bottom = image.get_pixel_checked(x, y + 1).copied();
+                        // Averages of a dangling row only show its own pixel.
+                        let average = || match bottom {
+                            Some(bottom) => {
+                                Pixel::from([0, 1, 2].map(|c| (top[c] + bottom[c]) / 2.0))
+                            }
+                            None => top,
+                        };
+                        let bottom = bottom.unwrap_or(options.background);
+                        let (glyph, fg, bg) = match options.glyph {
+                            Glyph::UpperHalf => ('▀', Some(top), Some(bottom)),
+                            Glyph::LowerHalf => ('▄', Some(bottom), Some(top)),
+                            Glyph::FullBlock => ('█', Some(average()), None),
+                            Glyph::SpaceBg => (' ', None, Some(average())),
+                        };
+                        line.push(Cell { glyph, fg, bg });
                     }
-                    string += "\n";
+                    lines.push(line);
                 }
             }
             Self::Gradient(gradient) => {
                 for y in (0..image.height()).step_by(2) {
+                    let mut line = Vec::new();
                     for x in 0..image.width() {
-                        let mut b = brightness(image.get_pixel(x, y));
+                        let mut b = options.brightness(image.get_pixel(x, y));
                         if let Some(bot) = image.get_pixel_checked(x, y + 1) {
-                            b = (b + brightness(bot)) / 2.0;
+                            b = options.mix(b, options.brightness(bot));
                         }
-                        let char_index = ((gradient.len() - 1) as f32 * b) as usize;
-                        string += &format!("{}\x1B[0m", gradient[char_index]);
-                        string += "\x1B[0m";
+                        // Quantized like colors, so that white reaches the last character despite
+                        // rounding, and HDR values outside [0, 1] are clamped.
+                        let level = to_u8(b) as usize;
+                        let glyph = match gradient.len() {
+                            // An empty gradient has nothing to draw with but blank cells.
+                            0 => ' ',
+                            len => gradient[level * (len - 1) / 255],
+                        };
+                        line.push(Cell::plain(glyph));
                     }
-                    string += "\n";
+                    lines.push(line);
                 }
             }
-            Self::Greyscale => {
+            Self::ColorGradient(colors) => {
                 for y in (0..image.height()).step_by(2) {
+                    let mut line = Vec::new();
                     for x in 0..image.width() {
-                        let b = brightness(image.get_pixel(x, y));
-                        string += &fg(&Pixel::from([b, b, b]));
+                        let mut b = options.brightness(image.get_pixel(x, y));
                         if let Some(bot) = image.get_pixel_checked(x, y + 1) {
-                            let b = brightness(bot);
-                            string += &bg(&Pixel::from([b, b, b]));
+                            b = options.mix(b, options.brightness(bot));
                         }
-                        string += "▀\x1B[0m";
+                        // Like empty gradients, no colors leave nothing to draw but blank cells.
+                        line.push(if colors.is_empty() {
+                            Cell::plain(' ')
+                        } else {
+                            Cell {
+                                glyph: '█',
+                                fg: Some(interpolate(colors, b)),
+                                bg: None,
+                            }
+                        });
+                    }
+                    lines.push(line);
+                }
+            }
+            Self::Greyscale => {
+                let grey = |pixel: &Pixel| {
+                    let b = options.brightness(pixel);
+                    Pixel::from([b, b, b])
+                };
+                for y in (0..image.height()).step_by(2) {
+                    let mut line = Vec::new();
+                    for x in 0..image.width() {
+                        line.push(Cell {
+                            glyph: '▀',
+                            fg: Some(grey(image.get_pixel(x, y))),
+                            bg: Some(grey(
+                                image
+                                    .get_pixel_checked(x, y + 1)
+                                    .unwrap_or(&options.background),
+                            )),
+                        });
                     }
-                    string += "\n";
+                    lines.push(line);
                 }
             }
             Self::DitheredBraille => {
-                greyscale(image);
-                floyd_steinberg(image);
-                string += &Self::Braille.apply(image);
+                greyscale(image, options);
+                dither(image, options);
+                lines = Self::Braille.cells(image, options);
+            }
+            Self::BayerColor => {
+                ordered_cube(image);
+                lines = Self::Color.cells(image, options);
             }
             Self::Dithered => {
-                greyscale(image);
-                floyd_steinberg(image);
-                string += &Self::Greyscale.apply(image);
+                greyscale(image, options);
+                dither(image, options);
+                lines = Self::Greyscale.cells(image, options);
             }
-            Self::Braille => {
-                for y in (0..image.height()).step_by(4) {
-                    for x in (0..image.width()).step_by(2) {
-                        // Coordinate offsets of the braille dots.
-                        let offsets = [
-                            (0, 0),
-                            (0, 1),
-                            (0, 2),
-                            (1, 0),
-                            (1, 1),
-                            (1, 2),
-                            (0, 3),
-                            (1, 3),
-                        ];
-                        let mut byte = 0u8;
-                        for index in 0..offsets.len() {
-                            let (i, j) = offsets[index];
-                            if let Some(pixel) = image.get_pixel_checked(x + i, y + j) {
-                                let b = brightness(pixel);
-                                let is_set = b < 0.5;
-                                byte = if is_set { byte | (1 << index) } else { byte }
-                            }
-                        }
-                        let char =
-                            char::from_u32(0x2800 + byte as u32).expect("failed to encode braille");
-                        string += &format!("{}", char);
+            Self::Subpixel => {
+                for y in (0..image.height()).step_by(2) {
+                    let mut line = Vec::new();
+                    for x in 0..image.width() {
+                        let top = options.brightness(image.get_pixel(x, y));
+                        let bottom = options.brightness(
+                            image
+                                .get_pixel_checked(x, y + 1)
+                                .unwrap_or(&options.background),
+                        );
+                        line.push(subpixel_cell(top, bottom));
+                    }
+                    lines.push(line);
+                }
+            }
+            Self::Mono => {
+                for y in 0..image.height() {
+                    let mut line = Vec::new();
+                    for x in 0..image.width() {
+                        let glyph = if options.brightness(image.get_pixel(x, y)) < 0.5 {
+                            ' '
+                        } else {
+                            '█'
+                        };
+                        line.extend([Cell::plain(glyph); 2]);
+                    }
+                    lines.push(line);
+                }
+            }
+            Self::Compact => {
+                for y in 0..image.height() {
+                    let mut line = Vec::new();
+                    for x in 0..image.width() {
+                        let pixel = *image.get_pixel(x, y);
+                        line.push(match options.glyph {
+                            Glyph::FullBlock => Cell {
+                                glyph: '█',
+                                fg: Some(pixel),
+                                bg: None,
+                            },
+                            _ => Cell {
+                                glyph: ' ',
+                                fg: None,
+                                bg: Some(pixel),
+                            },
+                        });
                     }
-                    string += "\n";
+                    lines.push(line);
                 }
             }
+            Self::Braille => {
+                lines = if options.braille_gradient {
+                    braille(image, options.alpha.as_ref(), false, |x, y, pixel| {
+                        let rank = BRAILLE_ORDER[(y % 4) as usize][(x % 2) as usize];
+                        options.brightness(pixel) < (rank as f32 + 0.5) / 8.0
+                    })
+                } else {
+                    braille(image, options.alpha.as_ref(), false, |_, _, pixel| {
+                        options.brightness(pixel) < options.threshold
+                    })
+                };
+            }
+            Self::BrailleEdges => {
+                let edges = sobel(image, options);
+                lines = braille(&edges, options.alpha.as_ref(), false, |_, _, pixel| {
+                    pixel.channels()[0] > EDGE_THRESHOLD
+                });
+            }
+            Self::ColorBraille => {
+                lines = braille(image, options.alpha.as_ref(), true, |_, _, pixel| {
+                    options.brightness(pixel) >= options.threshold
+                });
+            }
+            Self::Quadrant => lines = two_color(image, (2, 2), &QUADRANTS),
+            Self::Sextant => {
+                let glyphs: [char; 64] = std::array::from_fn(|mask| sextant(mask as u32));
+                lines = two_color(image, (2, 3), &glyphs);
+            }
         }
-        string
+        lines
     }
 }
 
-fn brightness(pixel: &Pixel) -> f32 {
-    0.299 * pixel.channels()[0] + 0.587 * pixel.channels()[1] + 0.114 * pixel.channels()[2]
+impl Display for Style {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Color => "color",
+            Self::Greyscale => "greyscale",
+            Self::Gradient(_) => "gradient",
+            Self::ColorGradient(_) => "color-gradient",
+            Self::Braille => "braille",
+            Self::DitheredBraille => "dithered-braille",
+            Self::Dithered => "dithered",
+            Self::Subpixel => "subpixel",
+            Self::BrailleEdges => "braille-edges",
+            Self::BayerColor => "bayer-color",
+            Self::ColorBraille => "color-braille",
+            Self::Quadrant => "quadrant",
+            Self::Sextant => "sextant",
+            Self::Mono => "mono",
+            Self::Compact => "compact",
+            Self::Auto => "auto",
+        };
+        write!(f, "{name}")
+    }
 }
 
-fn fg(color: &Pixel) -> String {
-    format!(
-        "\x1B[38;2;{};{};{}m",
-        (color.channels()[0] * 255.0) as u8,
-        (color.channels()[1] * 255.0) as u8,
-        (color.channels()[2] * 255.0) as u8,
-    )
+/// Single character of output with optional foreground and background colors.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Cell {
+    pub(crate) glyph: char,
+    pub(crate) fg: Option<Pixel>,
+    pub(crate) bg: Option<Pixel>,
 }
 
-fn bg(color: &Pixel) -> String {
-    format!(
-        "\x1B[48;2;{};{};{}m",
-        (color.channels()[0] * 255.0) as u8,
-        (color.channels()[1] * 255.0) as u8,
-        (color.channels()[2] * 255.0) as u8,
-    )
+impl Cell {
+    /// Cell using the terminal's default colors.
+    fn plain(glyph: char) -> Self {
+        Self {
+            glyph,
+            fg: None,
+            bg: None,
+        }
+    }
 }
 
-fn greyscale(image: &mut Rgb32FImage) {
-    for y in 0..image.height() {
-        for x in 0..image.width() {
-            let b = brightness(image.get_pixel(x, y));
-            *image.get_pixel_mut(x, y) = Pixel::from([b; Pixel::CHANNEL_COUNT as usize]);
+/// Color at `position` from 0 to 1 along evenly spaced `colors`, mixed in linear light.
+fn interpolate(colors: &[Pixel], position: f32) -> Pixel {
+    let scaled = position.clamp(0.0, 1.0) * (colors.len() - 1) as f32;
+    let i = (scaled as usize).min(colors.len().saturating_sub(2));
+    let t = scaled - i as f32;
+    let Some(next) = colors.get(i + 1) else {
+        return colors[i];
+    };
+    Pixel::from(std::array::from_fn(|c| {
+        let (a, b) = (to_linear(colors[i][c]), to_linear(next[c]));
+        to_srgb(a + (b - a) * t)
+    }))
+}
+
+/// Quadrant block glyphs indexed by the pixels they fill, bit 0 being the top left,
+/// bit 1 the top right, bit 2 the bottom left and bit 3 the bottom right pixel.
+pub(crate) const QUADRANTS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+/// Sextant glyph filling the pixels of a 2x3 block set in `mask`, numbered in row major order.
+/// The sextant range leaves out the patterns that already exist as half and full blocks.
+fn sextant(mask: u32) -> char {
+    match mask {
+        0 => ' ',
+        0b010101 => '▌',
+        0b101010 => '▐',
+        0b111111 => '█',
+        _ => {
+            let skipped = (mask > 0b010101) as u32 + (mask > 0b101010) as u32;
+            char::from_u32(0x1FB00 + mask - 1 - skipped).expect("failed to encode sextant")
         }
     }
 }
 
-fn quantize(pixel: &mut Pixel) -> Pixel {
-    let mut error = Pixel::from([0.0; Pixel::CHANNEL_COUNT as usize]);
-    for i in 0..Pixel::CHANNEL_COUNT as usize {
-        let value = pixel.channels()[i];
-        let q = if value < 0.5 { 0.0 } else { 1.0 };
-        pixel.channels_mut()[i] = q;
-        error.channels_mut()[i] = value - q;
+/// Split blocks of `width` x `height` pixels into the two colors that reproduce them best.
+/// Pixels of the foreground color are drawn by the glyph from `glyphs` indexed by their bits,
+/// numbered in row major order.
+fn two_color(image: &Rgb32FImage, (width, height): (u32, u32), glyphs: &[char]) -> Vec<Vec<Cell>> {
+    let mean = |pixels: &[Pixel]| {
+        let sum = pixels
+            .iter()
+            .fold([0.0; 3], |sum, pixel| [0, 1, 2].map(|c| sum[c] + pixel[c]));
+        Pixel::from(sum.map(|c| c / pixels.len() as f32))
+    };
+    let error = |pixels: &[Pixel], color: Pixel| -> f32 {
+        pixels
+            .iter()
+            .map(|pixel| (0..3).map(|c| (pixel[c] - color[c]).powi(2)).sum::<f32>())
+            .sum()
+    };
+    let mut lines = Vec::new();
+    for top in (0..image.height()).step_by(height as usize) {
+        let mut line = Vec::new();
+        for left in (0..image.width()).step_by(width as usize) {
+            // Blocks at the right and bottom edges may be cut off.
+            let mut present = 0;
+            let mut pixels = Vec::new();
+            for bit in 0..width * height {
+                let (x, y) = (left + bit % width, top + bit / width);
+                if let Some(pixel) = image.get_pixel_checked(x, y) {
+                    present |= 1 << bit;
+                    pixels.push((bit, *pixel));
+                }
+            }
+            let mut best = (f32::INFINITY, Cell::plain(' '));
+            // The full block goes first, so that uniform blocks are drawn with it instead of
+            // a partial glyph in the same color as its background.
+            let masks = (1..present).filter(|mask| mask & !present == 0);
+            for mask in std::iter::once(present).chain(masks) {
+                let (front, back): (Vec<_>, Vec<_>) =
+                    pixels.iter().partition(|(bit, _)| mask & 1 << bit != 0);
+                let front: Vec<Pixel> = front.into_iter().map(|(_, pixel)| pixel).collect();
+                let back: Vec<Pixel> = back.into_iter().map(|(_, pixel)| pixel).collect();
+                let fg = mean(&front);
+                let bg = (!back.is_empty()).then(|| mean(&back));
+                let total = error(&front, fg) + bg.map_or(0.0, |bg| error(&back, bg));
+                if total < best.0 {
+                    let glyph = glyphs[mask as usize];
+                    best = (
+                        total,
+                        Cell {
+                            glyph,
+                            fg: Some(fg),
+                            bg,
+                        },
+                    );
+                }
+            }
+            line.push(best.1);
+        }
+        lines.push(line);
     }
-    error
+    lines
 }
 
-fn floyd_steinberg(image: &mut Rgb32FImage) {
-    for y in 0..image.height() {
-        for x in 0..image.width() {
-            let old_pixel = image.get_pixel_mut(x, y);
-            let error = quantize(old_pixel);
-            let indices = [(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)];
-            for (i, j, f) in indices {
-                if x == 0 && i == -1 {
-                    continue;
+/// Pack blocks of 2x4 pixels into braille characters, setting the dots of all pixels matching `is_set`.
+/// If `colored`, the dots are drawn in the average color of their pixels.
+fn braille(
+    image: &Rgb32FImage,
+    alpha: Option<&GrayImage>,
+    colored: bool,
+    is_set: impl Fn(u32, u32, &Pixel) -> bool,
+) -> Vec<Vec<Cell>> {
+    let mut lines = Vec::new();
+    for y in (0..image.height()).step_by(4) {
+        let mut line = Vec::new();
+        for x in (0..image.width()).step_by(2) {
+            // Coordinate offsets of the braille dots.
+            let offsets = [
+                (0, 0),
+                (0, 1),
+                (0, 2),
+                (1, 0),
+                (1, 1),
+                (1, 2),
+                (0, 3),
+                (1, 3),
+            ];
+            let mut byte = 0u8;
+            let mut sum = [0.0; 3];
+            for (index, (i, j)) in offsets.into_iter().enumerate() {
+                let (px, py) = (x + i, y + j);
+                // Transparent pixels show the terminal background, like unset dots.
+                let opaque = || alpha.is_none_or(|alpha| alpha.get_pixel(px, py).0[0] >= 128);
+                if let Some(pixel) = image.get_pixel_checked(px, py)
+                    && opaque()
+                {
+                    byte = if is_set(px, py, pixel) {
+                        (0..3).for_each(|c| sum[c] += pixel[c]);
+                        byte | (1 << index)
+                    } else {
+                        byte
+                    }
                 }
-                if let Some(pixel) = image.get_pixel_mut_checked((x as i32 + i) as u32, y + j) {
-                    for c in 0..Pixel::CHANNEL_COUNT as usize {
-                        pixel.channels_mut()[c] += error.channels()[c] * f / 16.0;
+            }
+            let char = char::from_u32(0x2800 + byte as u32).expect("failed to encode braille");
+            let dots = byte.count_ones() as f32;
+            let fg = (colored && byte != 0).then(|| Pixel::from(sum.map(|c| c / dots)));
+            line.push(Cell {
+                glyph: char,
+                fg,
+                bg: None,
+            });
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Pick the block glyph and grey level that best reproduce the brightness of both cell halves,
+/// assuming a dark terminal background.
+fn subpixel_cell(top: f32, bottom: f32) -> Cell {
+    // Glyphs with the fraction of the top and bottom half they cover.
+    let mut glyphs = vec![(' ', 0.0, 0.0), ('▀', 1.0, 0.0), ('▔', 0.25, 0.0)];
+    for eighths in 1..=8u32 {
+        let glyph = char::from_u32(0x2580 + eighths).expect("failed to encode block");
+        let covered = eighths as f32 / 4.0;
+        glyphs.push((glyph, (covered - 1.0).max(0.0), covered.min(1.0)));
+    }
+    let mut best = (f32::INFINITY, Cell::plain(' '));
+    for (glyph, top_coverage, bottom_coverage) in glyphs {
+        let coverage = top_coverage * top_coverage + bottom_coverage * bottom_coverage;
+        let level = if coverage > 0.0 {
+            ((top * top_coverage + bottom * bottom_coverage) / coverage).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let error =
+            (top - level * top_coverage).powi(2) + (bottom - level * bottom_coverage).powi(2);
+        if error < best.0 {
+            let fg = (coverage > 0.0).then(|| Pixel::from([level; 3]));
+            best = (
+                error,
+                Cell {
+                    glyph,
+                    fg,
+                    bg: None,
+                },
+            );
+        }
+    }
+    best.1
+}
+
+/// Swatches of every distinct color in the image, wrapped after `columns` terminal columns.
+pub fn palette_strip(image: &Rgb32FImage, columns: u32, colors: ColorDepth) -> String {
+    let mut distinct: Vec<[u8; 3]> = image.pixels().map(|pixel| pixel.0.map(to_u8)).collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+    let per_line = (columns / 2).max(1) as usize;
+    let lines: Vec<Vec<Cell>> = distinct
+        .chunks(per_line)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .flat_map(|color| {
+                    let swatch = Cell {
+                        glyph: ' ',
+                        fg: None,
+                        bg: Some(Pixel::from(color.map(|c| c as f32 / 255.0))),
+                    };
+                    [swatch, swatch]
+                })
+                .collect()
+        })
+        .collect();
+    let mut output = Vec::new();
+    encode(&lines, colors, &mut output).expect("writing to memory cannot fail");
+    String::from_utf8(output).expect("encoded cells are UTF-8")
+}
+
+/// Longest SGR sequence setting a color, `\x1B[38;2;255;255;255m`.
+const MAX_COLOR_BYTES: usize = 19;
+
+/// Encode lines of cells as text with escape sequences and write them to `writer`.
+/// Colors are only emitted when they change from the previous cell, both in one sequence when
+/// they change together, and reset at the end of each line.
+fn encode(lines: &[Vec<Cell>], colors: ColorDepth, writer: &mut impl io::Write) -> io::Result<()> {
+    let mut string = String::new();
+    for line in lines {
+        // Enough for every cell to change both colors, plus the reset and line feed.
+        let capacity = line
+            .iter()
+            .map(|cell| {
+                let colors = cell.fg.is_some() as usize + cell.bg.is_some() as usize;
+                cell.glyph.len_utf8() + colors * MAX_COLOR_BYTES
+            })
+            .sum::<usize>()
+            + "\x1B[0m\n".len();
+        string.clear();
+        string.reserve(capacity);
+        let mut current_fg = None;
+        let mut current_bg = None;
+        for cell in line {
+            let next_fg = cell.fg.map(|color| ColorCode::new(&color, colors));
+            let next_bg = cell.bg.map(|color| ColorCode::new(&color, colors));
+            let fg_changed = next_fg != current_fg;
+            let bg_changed = next_bg != current_bg;
+            // Both colors share a single sequence when they change together.
+            if fg_changed || bg_changed {
+                string += "\x1B[";
+                if fg_changed {
+                    match next_fg {
+                        Some(code) => write!(string, "38;{code}").unwrap(),
+                        None => string += "39",
                     }
                 }
+                if fg_changed && bg_changed {
+                    string.push(';');
+                }
+                if bg_changed {
+                    match next_bg {
+                        Some(code) => write!(string, "48;{code}").unwrap(),
+                        None => string += "49",
+                    }
+                }
+                string.push('m');
+                current_fg = next_fg;
+                current_bg = next_bg;
             }
+            string.push(cell.glyph);
+        }
+        if current_fg.is_some() || current_bg.is_some() {
+            string += "\x1B[0m";
+        }
+        string += "\n";
+        writer.write_all(string.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Order in which braille dots light up with increasing darkness, indexed by `[y][x]`.
+/// Taken from a 4x4 Bayer matrix so that partially lit cells spread their dots evenly.
+const BRAILLE_ORDER: [[u8; 2]; 4] = [[0, 4], [6, 2], [1, 5], [7, 3]];
+
+/// Minimum normalized Sobel magnitude for a pixel to count as an edge.
+const EDGE_THRESHOLD: f32 = 0.1;
+
+/// Color as selected in an SGR sequence after the foreground or background code.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ColorCode {
+    Indexed(u8),
+    Rgb([u8; 3]),
+}
+
+impl ColorCode {
+    fn new(color: &Pixel, colors: ColorDepth) -> Self {
+        let rgb = color.0.map(to_u8);
+        match colors {
+            ColorDepth::Ansi256 => Self::Indexed(xterm_index(rgb)),
+            ColorDepth::TrueColor => Self::Rgb(rgb),
         }
     }
 }
+
+impl Display for ColorCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Indexed(index) => write!(f, "5;{index}"),
+            Self::Rgb([r, g, b]) => write!(f, "2;{r};{g};{b}"),
+        }
+    }
+}
+
+fn greyscale(image: &mut Rgb32FImage, options: &RenderOptions) {
+    for y in 0..image.height() {
+        for x in 0..image.width() {
+            let b = options.brightness(image.get_pixel(x, y));
+            *image.get_pixel_mut(x, y) = Pixel::from([b; Pixel::CHANNEL_COUNT as usize]);
+        }
+    }
+}
+
+/// Reduce the image to black and white with the dither algorithm from `options`,
+/// in linear light if they ask for it so that the average brightness is preserved.
+fn dither(image: &mut Rgb32FImage, options: &RenderOptions) {
+    if options.linear {
+        image
+            .pixels_mut()
+            .for_each(|pixel| *pixel = Pixel::from(pixel.0.map(to_linear)));
+    }
+    options
+        .dither
+        .apply(image, options.serpentine, options.seed);
+}
+
+/// Greyscale edge magnitude of the Sobel operator, normalized to [0, 1].
+/// Pixels outside the image are clamped to the border.
+pub fn sobel(image: &Rgb32FImage, options: &RenderOptions) -> Rgb32FImage {
+    let (width, height) = image.dimensions();
+    let at = |x: i64, y: i64| {
+        let x = x.clamp(0, width as i64 - 1) as u32;
+        let y = y.clamp(0, height as i64 - 1) as u32;
+        options.brightness(image.get_pixel(x, y))
+    };
+    // Largest possible magnitude, reached on a diagonal black/white step.
+    let max = 4.0 * 2f32.sqrt();
+    Rgb32FImage::from_fn(width, height, |x, y| {
+        let (x, y) = (x as i64, y as i64);
+        let gx = at(x + 1, y - 1) + 2.0 * at(x + 1, y) + at(x + 1, y + 1)
+            - at(x - 1, y - 1)
+            - 2.0 * at(x - 1, y)
+            - at(x - 1, y + 1);
+        let gy = at(x - 1, y + 1) + 2.0 * at(x, y + 1) + at(x + 1, y + 1)
+            - at(x - 1, y - 1)
+            - 2.0 * at(x, y - 1)
+            - at(x + 1, y - 1);
+        let magnitude = (gx * gx + gy * gy).sqrt() / max;
+        Pixel::from([magnitude; Pixel::CHANNEL_COUNT as usize])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subpixel_blocks_follow_both_halves() {
+        let cell = |top, bottom| {
+            let cell = subpixel_cell(top, bottom);
+            (cell.glyph, cell.fg.map(|fg| fg[0]))
+        };
+        assert_eq!(cell(0.0, 0.0), (' ', None));
+        assert_eq!(cell(1.0, 0.0), ('▀', Some(1.0)));
+        assert_eq!(cell(0.0, 1.0), ('▄', Some(1.0)));
+        assert_eq!(cell(1.0, 1.0), ('█', Some(1.0)));
+        // A dimmer top half is drawn as a block reaching only partway into it.
+        assert_eq!(cell(0.5, 1.0), ('▆', Some(1.0)));
+        assert_eq!(cell(0.25, 1.0), ('▅', Some(1.0)));
+    }
+
+    #[test]
+    fn sextants_reuse_half_and_full_blocks() {
+        assert_eq!(sextant(0), ' ');
+        assert_eq!(sextant(0b111111), '█');
+        assert_eq!(sextant(0b010101), '▌');
+        assert_eq!(sextant(0b101010), '▐');
+        // The range starts with the top left pixel and ends with all but it.
+        assert_eq!(sextant(0b000001), '\u{1FB00}');
+        assert_eq!(sextant(0b111110), '\u{1FB3B}');
+    }
+}
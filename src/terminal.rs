@@ -0,0 +1,174 @@
+use std::io::IsTerminal;
+
+/// Brightness of the terminal background.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum, serde::Deserialize))]
+#[cfg_attr(feature = "cli", serde(rename_all = "kebab-case"))]
+pub enum Theme {
+    /// Light background with dark text.
+    Light,
+
+    /// Dark background with light text.
+    Dark,
+}
+
+/// Best kind of output a terminal is known to display.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Capability {
+    /// Kitty graphics protocol.
+    Kitty,
+    /// Sixel graphics.
+    Sixel,
+    /// 24 bit color escapes.
+    TrueColor,
+    /// The xterm 256 color palette.
+    Ansi256,
+    /// Nothing beyond plain text is known to work.
+    Text,
+}
+
+/// Detect what the terminal can display from environment variables looked up with `var`,
+/// checked in order of precedence:
+/// 1. `KITTY_WINDOW_ID`, `TERM=xterm-kitty` or `TERM_PROGRAM` of WezTerm or ghostty: kitty graphics.
+/// 2. `TERM` of foot, mlterm or containing `sixel`: sixel graphics.
+/// 3. `COLORTERM` of `truecolor` or `24bit`: 24 bit color.
+/// 4. `TERM` ending in `256color`: 256 colors.
+/// 5. Otherwise plain text.
+pub fn detect_capability(var: impl Fn(&str) -> Option<String>) -> Capability {
+    let term = var("TERM").unwrap_or_default();
+    let program = var("TERM_PROGRAM").unwrap_or_default();
+    if var("KITTY_WINDOW_ID").is_some()
+        || term == "xterm-kitty"
+        || matches!(program.as_str(), "WezTerm" | "ghostty")
+    {
+        return Capability::Kitty;
+    }
+    if term.starts_with("foot") || term.starts_with("mlterm") || term.contains("sixel") {
+        return Capability::Sixel;
+    }
+    match var("COLORTERM").as_deref() {
+        Some("truecolor" | "24bit") => Capability::TrueColor,
+        _ if term.ends_with("256color") => Capability::Ansi256,
+        _ => Capability::Text,
+    }
+}
+
+/// Whether the terminal likely uses a font without the Unicode 13 sextant characters,
+/// judging by environment variables looked up with `var`.
+pub fn lacks_sextants(var: impl Fn(&str) -> Option<String>) -> bool {
+    let term = var("TERM").unwrap_or_default();
+    term == "linux"
+        || term.starts_with("vt")
+        || var("TERM_PROGRAM").as_deref() == Some("Apple_Terminal")
+}
+
+/// Terminal multiplexer between the program and the terminal, which swallows escape
+/// sequences it does not know unless they are passed through.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Multiplexer {
+    Tmux,
+    Screen,
+}
+
+/// Detect a multiplexer from `TMUX` or `STY`, looked up with `var`.
+pub fn detect_multiplexer(var: impl Fn(&str) -> Option<String>) -> Option<Multiplexer> {
+    let set = |name| var(name).is_some_and(|value| !value.is_empty());
+    if set("TMUX") {
+        Some(Multiplexer::Tmux)
+    } else if set("STY") {
+        Some(Multiplexer::Screen)
+    } else {
+        None
+    }
+}
+
+/// Detect the terminal theme from the environment.
+pub fn detect_theme() -> Option<Theme> {
+    std::env::var("COLORFGBG")
+        .ok()
+        .and_then(|value| theme_from_colorfgbg(&value))
+}
+
+/// Size of the terminal as `(columns, rows)`, or `None` when output is not a terminal.
+pub fn terminal_size() -> Option<(u32, u32)> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    let (columns, rows) = crossterm::terminal::size().ok()?;
+    Some((columns as u32, rows as u32))
+}
+
+/// Notices changes of the size returned by `query`, e.g. [`terminal_size`], so that live output
+/// such as animations can be fit to a resized terminal.
+pub struct SizeWatch<F> {
+    query: F,
+    size: Option<(u32, u32)>,
+}
+
+impl<F: FnMut() -> Option<(u32, u32)>> SizeWatch<F> {
+    /// Start watching from the current size.
+    pub fn new(mut query: F) -> Self {
+        let size = query();
+        Self { query, size }
+    }
+
+    /// The new size if it differs from the one seen last, `None` while it stays the same
+    /// or cannot be determined.
+    pub fn changed(&mut self) -> Option<(u32, u32)> {
+        let size = (self.query)()?;
+        if self.size == Some(size) {
+            return None;
+        }
+        self.size = Some(size);
+        Some(size)
+    }
+}
+
+/// Terminal size as `(columns, rows)` from the `COLUMNS` and `LINES` environment variables
+/// looked up with `var`, as exported by some shells and CI systems. Rows are `None` without
+/// `LINES`.
+pub fn size_from_env(var: impl Fn(&str) -> Option<String>) -> Option<(u32, Option<u32>)> {
+    let read = |name| {
+        var(name)
+            .and_then(|value| value.trim().parse::<u32>().ok())
+            .filter(|&size| size > 0)
+    };
+    Some((read("COLUMNS")?, read("LINES")))
+}
+
+/// Size of the terminal window in pixels as `(width, height)`, as in the `CSI 14 t` report,
+/// if the terminal reports it.
+pub fn window_pixels() -> Option<(u32, u32)> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    let size = crossterm::terminal::window_size().ok()?;
+    if size.width == 0 || size.height == 0 {
+        return None;
+    }
+    Some((size.width as u32, size.height as u32))
+}
+
+/// Size of a terminal cell in pixels as `(width, height)`, if the terminal reports it.
+pub fn cell_pixels() -> Option<(u32, u32)> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    let size = crossterm::terminal::window_size().ok()?;
+    if size.width == 0 || size.height == 0 || size.columns == 0 || size.rows == 0 {
+        return None;
+    }
+    let width = size.width / size.columns;
+    let height = size.height / size.rows;
+    Some((width as u32, height as u32))
+}
+
+/// Parse `COLORFGBG` as set by rxvt, Konsole and others, e.g. `15;0` for light text on black.
+/// The last field is the background color as an index into the 16 color palette.
+fn theme_from_colorfgbg(value: &str) -> Option<Theme> {
+    let background: u8 = value.rsplit(';').next()?.parse().ok()?;
+    match background {
+        0..=6 | 8 => Some(Theme::Dark),
+        _ => Some(Theme::Light),
+    }
+}
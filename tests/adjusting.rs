@@ -0,0 +1,110 @@
+//! Adjustments of channel values, which have to keep them displayable.
+
+use image::{DynamicImage, Rgb, Rgb32FImage};
+use ttview::adjusting::*;
+
+/// Single row of grey pixels with the given values.
+fn greys(values: &[f32]) -> Rgb32FImage {
+    Rgb32FImage::from_fn(values.len() as u32, 1, |x, _| Rgb([values[x as usize]; 3]))
+}
+
+/// Adjustment of an image with fixed parameters.
+type Adjustment = fn(&mut Rgb32FImage);
+
+fn values(image: &Rgb32FImage) -> Vec<f32> {
+    image.pixels().map(|pixel| pixel[0]).collect()
+}
+
+fn assert_close(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < 1e-5, "{actual:?} != {expected:?}");
+    }
+}
+
+#[test]
+fn out_of_range_values_are_clamped() {
+    let adjustments: [(&str, Adjustment); 4] = [
+        ("brightness", |image| brightness(image, 0.0)),
+        ("contrast", |image| contrast(image, 1.0)),
+        ("saturation", |image| saturation(image, 1.0)),
+        ("window", |image| window(image, "0,1".parse().unwrap())),
+    ];
+    for (name, adjust) in adjustments {
+        let mut image = greys(&[2.0, -1.0, 0.5]);
+        adjust(&mut image);
+        assert_close(&values(&image), &[1.0, 0.0, 0.5]);
+        assert!(
+            image
+                .pixels()
+                .all(|pixel| pixel.0.iter().all(|c| (0.0..=1.0).contains(c))),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn tonemap_compresses_highlights() {
+    let mut image = greys(&[2.0, 0.5, 0.0]);
+    tonemap(&mut image);
+    // The brightest pixel becomes white and the others darken to make room for it.
+    assert_close(&values(&image), &[1.0, 0.375, 0.0]);
+    // Images without highlights are left as they are.
+    let mut image = greys(&[1.0, 0.5]);
+    tonemap(&mut image);
+    assert_close(&values(&image), &[1.0, 0.5]);
+}
+
+#[test]
+fn exposure_and_gamma() {
+    let mut image = greys(&[0.25, 1.0]);
+    exposure(&mut image, 1.0);
+    assert_close(&values(&image), &[0.5, 2.0]);
+    let mut image = greys(&[0.0, 0.25, 1.0]);
+    gamma(&mut image, 2.0);
+    assert_close(&values(&image), &[0.0, 0.5, 1.0]);
+}
+
+#[test]
+fn invert_normalize_and_binarize() {
+    let mut image = greys(&[0.0, 0.25, 0.5]);
+    invert(&mut image);
+    assert_close(&values(&image), &[1.0, 0.75, 0.5]);
+    let mut image = greys(&[0.0, 0.25, 0.5]);
+    normalize(&mut image);
+    assert_close(&values(&image), &[0.0, 0.5, 1.0]);
+    let mut image = greys(&[0.2, 0.6]);
+    binarize(&mut image, 0.5);
+    assert_close(&values(&image), &[0.0, 1.0]);
+}
+
+#[test]
+fn window_stretches_its_range() {
+    let mut image = greys(&[0.0, 0.1, 0.25, 0.375, 0.5, 0.625, 0.75, 0.9, 1.0]);
+    window(&mut image, "0.25,0.75".parse().unwrap());
+    // Clamped outside, the middle stays in place and the slope inside is 1 / 0.5.
+    assert_close(
+        &values(&image),
+        &[0.0, 0.0, 0.0, 0.25, 0.5, 0.75, 1.0, 1.0, 1.0],
+    );
+}
+
+#[test]
+fn gamma_of_a_typical_display() {
+    let mut image = greys(&[0.0, 0.5, 1.0]);
+    gamma(&mut image, 2.2);
+    assert_close(&values(&image), &[0.0, 0.5f32.powf(1.0 / 2.2), 1.0]);
+    assert!((values(&image)[1] - 0.73).abs() < 0.001);
+}
+
+#[test]
+fn despeckle_removes_isolated_pixels() {
+    // Flat grey with a white speck in the middle and a black one in the corner.
+    let image = Rgb32FImage::from_fn(5, 5, |x, y| match (x, y) {
+        (2, 2) => Rgb([1.0; 3]),
+        (0, 0) => Rgb([0.0; 3]),
+        _ => Rgb([0.5; 3]),
+    });
+    let despeckled = despeckle(&DynamicImage::ImageRgb32F(image)).to_rgb32f();
+    assert!(despeckled.pixels().all(|pixel| pixel.0 == [0.5; 3]));
+}
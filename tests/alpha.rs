@@ -0,0 +1,47 @@
+//! Braille styles leaving out the dots of transparent pixels.
+
+use image::{GrayImage, Luma, Rgb32FImage};
+use ttview::Style;
+use ttview::styling::RenderOptions;
+
+/// Black 8x8 icon, two braille cells wide and high, with its top left corner transparent.
+fn icon() -> (Rgb32FImage, GrayImage) {
+    let image = Rgb32FImage::new(8, 8);
+    let alpha = GrayImage::from_fn(8, 8, |x, y| Luma([if x + y < 4 { 0 } else { 255 }]));
+    (image, alpha)
+}
+
+fn render(style: &Style, alpha: Option<GrayImage>) -> String {
+    let (mut image, _) = icon();
+    let options = RenderOptions {
+        alpha,
+        ..RenderOptions::default()
+    };
+    style.apply_to_string(&mut image, &options)
+}
+
+#[test]
+fn opaque_without_alpha() {
+    assert_eq!(render(&Style::Braille, None), "⣿⣿⣿⣿\n⣿⣿⣿⣿\n");
+}
+
+#[test]
+fn transparent_corner_has_no_dots() {
+    let (_, alpha) = icon();
+    assert_eq!(render(&Style::Braille, Some(alpha)), "⢀⣴⣿⣿\n⣿⣿⣿⣿\n");
+}
+
+#[test]
+fn every_braille_style_leaves_out_transparent_pixels() {
+    let (_, alpha) = icon();
+    let transparent = alpha.pixels().filter(|pixel| pixel.0[0] == 0).count() as u32;
+    for style in [Style::Braille, Style::DitheredBraille] {
+        assert!(style.uses_alpha());
+        let dots: u32 = render(&style, Some(alpha.clone()))
+            .chars()
+            .filter(|&c| ('\u{2800}'..='\u{28FF}').contains(&c))
+            .map(|c| (c as u32 - 0x2800).count_ones())
+            .sum();
+        assert_eq!(dots, 64 - transparent, "{style}");
+    }
+}
@@ -0,0 +1,696 @@
+//! Output of the command line tool.
+#![cfg(feature = "cli")]
+
+use clap::ValueEnum;
+use image::codecs::gif::GifEncoder;
+use image::{Delay, Frame, ImageFormat, Rgb, RgbImage, Rgba, RgbaImage};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use ttview::{Filter, Style};
+
+/// Small grey PPM image written to the temporary directory, one per test running in parallel.
+fn fixture(test: &str) -> PathBuf {
+    let name = format!("ttview-{test}-{}.ppm", std::process::id());
+    let path = std::env::temp_dir().join(name);
+    let mut ppm = b"P6 4 4 255\n".to_vec();
+    ppm.extend([128; 4 * 4 * 3]);
+    std::fs::write(&path, ppm).unwrap();
+    path
+}
+
+/// Two frame GIF of 2x2 pixels, red and green on the left and blue and white on the right.
+fn animation(test: &str) -> PathBuf {
+    let name = format!("ttview-{test}-{}.gif", std::process::id());
+    let path = std::env::temp_dir().join(name);
+    let frame = |left: [u8; 3], right: [u8; 3]| {
+        let image = RgbaImage::from_fn(2, 2, |x, _| {
+            let [r, g, b] = if x == 0 { left } else { right };
+            Rgba([r, g, b, 255])
+        });
+        Frame::from_parts(image, 0, 0, Delay::from_numer_denom_ms(100, 1))
+    };
+    let mut encoder = GifEncoder::new(std::fs::File::create(&path).unwrap());
+    encoder
+        .encode_frames([
+            frame([255, 0, 0], [0, 0, 255]),
+            frame([0, 255, 0], [255, 255, 255]),
+        ])
+        .unwrap();
+    path
+}
+
+fn ttview(test: &str, args: &[&str]) -> Output {
+    let path = fixture(test);
+    let output = Command::new(env!("CARGO_BIN_EXE_ttview"))
+        .args(["--reproducible", "--quiet", "--width", "4"])
+        .args(args)
+        .args([&path, &path, &path])
+        .output()
+        .unwrap();
+    std::fs::remove_file(path).unwrap();
+    output
+}
+
+fn run(test: &str, args: &[&str]) -> String {
+    let output = ttview(test, args);
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Number of blank lines in each run of them, images being two lines high at this size.
+fn separators(output: &str) -> Vec<usize> {
+    let mut runs = Vec::new();
+    let mut blank = 0;
+    for line in output.lines() {
+        if line.is_empty() {
+            blank += 1;
+        } else if blank > 0 {
+            runs.push(blank);
+            blank = 0;
+        }
+    }
+    runs.push(blank);
+    runs
+}
+
+#[test]
+fn reproducible_output_ignores_the_environment() {
+    let run = |columns: &str, term: &str| {
+        let path = fixture("reproducible");
+        let output = Command::new(env!("CARGO_BIN_EXE_ttview"))
+            .args(["--reproducible", "--width", "4", "--style", "auto"])
+            .arg(&path)
+            .env("COLUMNS", columns)
+            .env("TERM", term)
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert!(output.status.success());
+        output.stdout
+    };
+    let first = run("20", "xterm-kitty");
+    assert!(!first.is_empty());
+    assert_eq!(first, run("200", "dumb"));
+}
+
+#[test]
+fn images_are_rotated_and_flipped() {
+    // Red on the left and blue on the right.
+    let path = std::env::temp_dir().join(format!("ttview-transform-{}.ppm", std::process::id()));
+    let mut ppm = b"P6 2 2 255\n".to_vec();
+    ppm.extend([[255, 0, 0], [0, 0, 255], [255, 0, 0], [0, 0, 255]].concat());
+    std::fs::write(&path, ppm).unwrap();
+    let render = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_ttview"))
+            .args(["--reproducible", "--width", "2"])
+            .args(args)
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let (red, blue) = ("38;2;255;0;0", "38;2;0;0;255");
+    let position = |output: &str, color: &str| output.find(color).unwrap();
+    let plain = render(&[]);
+    assert!(position(&plain, red) < position(&plain, blue), "{plain}");
+    let flipped = render(&["--flip", "horizontal"]);
+    assert!(
+        position(&flipped, blue) < position(&flipped, red),
+        "{flipped}"
+    );
+    // Blue on the top and red on the bottom after turning the image a quarter counterclockwise.
+    let rotated = render(&["--rotate", "270"]);
+    assert!(
+        rotated.contains(blue) && !rotated.contains(red),
+        "{rotated}"
+    );
+    assert!(rotated.contains("48;2;255;0;0"), "{rotated}");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn single_frame_of_an_animation() {
+    let path = animation("frame");
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_ttview"))
+            .args(["--width", "2"])
+            .args(args)
+            .arg(&path)
+            .output()
+            .unwrap()
+    };
+    let second = run(&["--frame", "1"]);
+    assert!(second.status.success());
+    let output = String::from_utf8(second.stdout).unwrap();
+    assert!(output.contains("38;2;0;255;0"), "{output}");
+    assert!(!output.contains("38;2;255;0;0"), "{output}");
+    let still = run(&["--still", "--asciinema"]);
+    assert!(still.status.success());
+    let recording = String::from_utf8(still.stdout).unwrap();
+    assert_eq!(recording.lines().count(), 2, "{recording}");
+    assert!(recording.contains("255;0;0"), "{recording}");
+    let missing = run(&["--frame", "2"]);
+    assert!(!missing.status.success());
+    let error = String::from_utf8(missing.stderr).unwrap();
+    assert!(error.contains("frame 2 is out of range"), "{error}");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn every_frame_is_flipped() {
+    let path = animation("flip");
+    let output = Command::new(env!("CARGO_BIN_EXE_ttview"))
+        .args(["--reproducible", "--width", "2", "--asciinema"])
+        .args(["--flip", "horizontal"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert!(output.status.success());
+    let recording = String::from_utf8(output.stdout).unwrap();
+    // Header, clearing the screen, then one event per frame.
+    let frames: Vec<&str> = recording.lines().skip(2).collect();
+    assert_eq!(frames.len(), 2, "{recording}");
+    // The colors on the right of each frame come first.
+    assert!(
+        frames[0].contains("\\u001b[H\\u001b[38;2;0;0;255"),
+        "{}",
+        frames[0]
+    );
+    assert!(
+        frames[1].contains("\\u001b[H\\u001b[38;2;255;255;255"),
+        "{}",
+        frames[1]
+    );
+}
+
+#[test]
+fn one_blank_line_by_default() {
+    assert_eq!(separators(&run("default-gap", &[])), [1, 1, 1]);
+}
+
+#[test]
+fn gap_between_stacked_images() {
+    let output = run("stacked-gap", &["--gap", "2"]);
+    assert_eq!(output.lines().filter(|line| !line.is_empty()).count(), 6);
+    assert_eq!(separators(&output), [2, 2, 2]);
+}
+
+#[test]
+fn gap_between_rows_and_columns() {
+    let output = run("column-gap", &["--gap", "3", "--columns", "2"]);
+    assert_eq!(separators(&output), [3, 3]);
+    let first = output.lines().next().unwrap();
+    assert!(first.contains("\x1B[0m   \x1B["), "{first:?}");
+}
+
+#[test]
+fn captured_animation_has_every_frame() {
+    let path = animation("capture");
+    for (protocol, payload) in [("sixel", "\x1BP"), ("kitty", "\x1B_Ga=T")] {
+        let output = Command::new(env!("CARGO_BIN_EXE_ttview"))
+            .args(["--width", "2", "--loop", "2", "--protocol", protocol])
+            .arg(&path)
+            .env_remove("TMUX")
+            .env_remove("STY")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let capture = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(capture.matches(payload).count(), 4, "{protocol}");
+        assert_eq!(capture.matches("\x1B[H").count(), 5, "{protocol}");
+    }
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn wide_gradient_is_rejected() {
+    let output = ttview("wide-gradient", &["--gradient", " .字@"]);
+    assert!(!output.status.success());
+    let error = String::from_utf8(output.stderr).unwrap();
+    assert!(error.contains("'字' (U+5B57)"), "{error}");
+}
+
+/// Run with `input` piped to stdin as the only file.
+fn ttview_stdin(args: &[&str], input: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ttview"))
+        .args(["--reproducible", "--quiet"])
+        .args(args)
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn raw_rgb_from_stdin() {
+    // Red, green on top of blue, white.
+    let pixels = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+    let args = ["--width", "2", "--stdin-format", "raw", "--raw-size", "2x2"];
+    let output = ttview_stdin(&args, &pixels);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "\x1B[38;2;255;0;0;48;2;0;0;255m▀\x1B[38;2;0;255;0;48;2;255;255;255m▀\x1B[0m\n\n"
+    );
+}
+
+#[test]
+fn raw_size_must_match() {
+    let args = ["--width", "2", "--stdin-format", "raw", "--raw-size", "2x2"];
+    let output = ttview_stdin(&args, &[255, 0, 0]);
+    assert!(!output.status.success());
+    let error = String::from_utf8(output.stderr).unwrap();
+    assert!(error.contains("expected 12 bytes"), "{error}");
+}
+
+#[test]
+fn bench_fails_on_missing_file() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ttview"))
+        .args(["bench", "/nonexistent/ttview.png"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let error = String::from_utf8(output.stderr).unwrap();
+    assert!(error.contains("/nonexistent/ttview.png"), "{error}");
+}
+
+#[test]
+fn bench_has_a_row_per_style_and_filter() {
+    let path = fixture("bench");
+    let output = Command::new(env!("CARGO_BIN_EXE_ttview"))
+        .args(["--width", "4", "bench"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert!(output.status.success());
+    let table = String::from_utf8(output.stdout).unwrap();
+    let mut lines = table.lines();
+    let header: Vec<&str> = lines.next().unwrap().split_whitespace().collect();
+    assert_eq!(header, ["style", "filter", "resize", "render", "bytes"]);
+    let rows: Vec<(String, String)> = lines
+        .map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            assert_eq!(columns.len(), 5, "{line}");
+            assert!(columns[4].parse::<usize>().unwrap() > 0, "{line}");
+            (columns[0].to_string(), columns[1].to_string())
+        })
+        .collect();
+    // Auto only picks one of the other styles, so it has no rows of its own.
+    let mut expected = Vec::new();
+    for filter in Filter::value_variants() {
+        let filter = filter.to_possible_value().unwrap().get_name().to_string();
+        for style in Style::value_variants() {
+            if !matches!(style, Style::Auto) {
+                expected.push((style.to_string(), filter.clone()));
+            }
+        }
+    }
+    assert_eq!(rows, expected);
+}
+
+#[test]
+fn reproducible_output_does_not_play_animations() {
+    let path = animation("reproducible-loop");
+    let output = Command::new(env!("CARGO_BIN_EXE_ttview"))
+        .args(["--reproducible", "--quiet", "--width", "2", "--loop", "2"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("\x1B[H"), "{stdout:?}");
+    assert!(!stdout.contains("\x1B[?25l"), "{stdout:?}");
+    assert_eq!(stdout.matches('▀').count(), 2, "{stdout:?}");
+}
+
+#[test]
+fn missing_file_fails() {
+    let path = fixture("missing");
+    let output = Command::new(env!("CARGO_BIN_EXE_ttview"))
+        .args(["--reproducible", "--quiet", "--width", "4"])
+        .args([path.as_path(), Path::new("/nonexistent/ttview.png")])
+        .output()
+        .unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert!(!output.status.success());
+    // Files that load are still shown.
+    assert!(!output.stdout.is_empty());
+    let error = String::from_utf8(output.stderr).unwrap();
+    assert!(error.contains("/nonexistent/ttview.png"), "{error}");
+}
+
+#[test]
+fn no_color_environment_variable() {
+    let run = |no_color: &str, args: &[&str]| {
+        let path = fixture(&format!("no-color-{}", args.len()));
+        let output = Command::new(env!("CARGO_BIN_EXE_ttview"))
+            .args(["--no-config", "--quiet", "--width", "4"])
+            .args(args)
+            .arg(&path)
+            .env("NO_COLOR", no_color)
+            .output()
+            .unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let plain = run("1", &[]);
+    assert!(!plain.is_empty());
+    assert!(!plain.contains('\x1B'), "{plain:?}");
+    // An empty value does not count, and an explicit style still wins.
+    assert!(run("", &[]).contains('\x1B'));
+    assert!(run("1", &["--style", "color"]).contains('\x1B'));
+}
+
+#[test]
+fn brightness_out_of_range_is_rejected() {
+    assert!(
+        ttview("brightness-low", &["--brightness", "-0.5"])
+            .status
+            .success()
+    );
+    let output = ttview("brightness-high", &["--brightness", "1.5"]);
+    assert!(!output.status.success());
+    let error = String::from_utf8(output.stderr).unwrap();
+    assert!(error.contains("from -1 to 1"), "{error}");
+}
+
+#[test]
+fn config_file_is_ignored_with_no_config() {
+    let home = std::env::temp_dir().join(format!("ttview-config-{}", std::process::id()));
+    std::fs::create_dir_all(home.join("ttview")).unwrap();
+    std::fs::write(home.join("ttview/config.toml"), "style = \"braille\"\n").unwrap();
+    let path = fixture("config");
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_ttview"))
+            .args(["--quiet", "--width", "4"])
+            .args(args)
+            .arg(&path)
+            .env("XDG_CONFIG_HOME", &home)
+            .env_remove("NO_COLOR")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let braille = |output: &str| {
+        output
+            .chars()
+            .any(|c| ('\u{2800}'..='\u{28FF}').contains(&c))
+    };
+    let configured = run(&[]);
+    let overridden = run(&["--style", "color"]);
+    let ignored = run(&["--no-config"]);
+    std::fs::remove_dir_all(home).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert!(braille(&configured), "{configured:?}");
+    assert!(
+        overridden.contains('▀') && !braille(&overridden),
+        "{overridden:?}"
+    );
+    assert!(ignored.contains('▀') && !braille(&ignored), "{ignored:?}");
+}
+
+#[test]
+fn width_and_height_set_the_size() {
+    let path = fixture("size");
+    let size = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_ttview"))
+            .arg("--quiet")
+            .args(args)
+            .arg(&path)
+            .env_remove("NO_COLOR")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let output = String::from_utf8(output.stdout).unwrap();
+        let rows: Vec<usize> = output
+            .lines()
+            .map(|line| line.matches('▀').count())
+            .filter(|&cells| cells > 0)
+            .collect();
+        (rows[0], rows.len())
+    };
+    // The square image stays square, at two pixels per cell, unless both are given.
+    assert_eq!(size(&["--width", "8"]), (8, 4));
+    assert_eq!(size(&["--height", "8"]), (8, 4));
+    assert_eq!(size(&["--width", "8", "--height", "2"]), (8, 1));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn braille_style_draws_braille() {
+    let output = run("braille", &["--style", "braille"]);
+    let cells: Vec<char> = output.chars().filter(|c| !c.is_whitespace()).collect();
+    assert!(!cells.is_empty());
+    assert!(
+        cells.iter().all(|c| ('\u{2800}'..='\u{28FF}').contains(c)),
+        "{output:?}"
+    );
+}
+
+#[test]
+fn raw_out_has_the_bytes_of_the_resized_image() {
+    let path = fixture("raw-out");
+    let output = Command::new(env!("CARGO_BIN_EXE_ttview"))
+        .args([
+            "--reproducible",
+            "--quiet",
+            "--width",
+            "6",
+            "--raw-out",
+            "-",
+        ])
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert!(output.status.success());
+    // 6x6 pixels of RGB, in place of the rendered text.
+    assert_eq!(output.stdout.len(), 6 * 6 * 3);
+    assert!(output.stdout.iter().all(|&byte| byte == 128));
+}
+
+#[test]
+fn debug_escapes_are_visible() {
+    let output = run("debug-escapes", &["--debug-escapes"]);
+    assert!(!output.contains('\x1B'), "{output:?}");
+    assert!(
+        output.contains("\\x1b[38;2;128;128;128;48;2;128;128;128m▀"),
+        "{output}"
+    );
+    assert!(output.contains("\\x1b[0m"), "{output}");
+}
+
+/// JPEG of 16x8 pixels, red on the left and blue on the right, whose EXIF orientation says
+/// to turn it 90 degrees clockwise to be upright.
+fn rotated_photo(test: &str) -> PathBuf {
+    let name = format!("ttview-{test}-{}.jpg", std::process::id());
+    let path = std::env::temp_dir().join(name);
+    let image = RgbImage::from_fn(16, 8, |x, _| {
+        if x < 8 {
+            Rgb([255, 0, 0])
+        } else {
+            Rgb([0, 0, 255])
+        }
+    });
+    let mut jpeg = Vec::new();
+    image
+        .write_to(&mut std::io::Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+        .unwrap();
+    // APP1 segment right after the start of image, holding a big endian TIFF header and
+    // a single IFD entry: orientation (0x0112), one SHORT of value 6.
+    let mut exif = b"\xFF\xE1\x00\x22Exif\x00\x00MM\x00\x2A\x00\x00\x00\x08\x00\x01".to_vec();
+    exif.extend(b"\x01\x12\x00\x03\x00\x00\x00\x01\x00\x06\x00\x00\x00\x00\x00\x00");
+    jpeg.splice(2..2, exif);
+    std::fs::write(&path, jpeg).unwrap();
+    path
+}
+
+#[test]
+fn photos_are_turned_upright() {
+    let path = rotated_photo("orientation");
+    let pixels = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_ttview"))
+            .args(["--reproducible", "--quiet", "--zoom", "1", "--raw-out", "-"])
+            .args(args)
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        output.stdout
+    };
+    let red = |pixel: &[u8]| pixel[0] > 200 && pixel[2] < 60;
+    let blue = |pixel: &[u8]| pixel[2] > 200 && pixel[0] < 60;
+    // Turned to 8x16 pixels, red ends up on top and blue at the bottom.
+    let upright = pixels(&[]);
+    assert_eq!(upright.len(), 8 * 16 * 3);
+    assert!(red(&upright[..3]) && blue(&upright[upright.len() - 3..]));
+    let stored = pixels(&["--no-auto-orient"]);
+    assert_eq!(stored.len(), 16 * 8 * 3);
+    assert!(red(&stored[..3]) && blue(&stored[15 * 3..16 * 3]));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn crop_must_lie_inside_the_image() {
+    // 4x2 pixels, red on the left half and blue on the right.
+    let path = std::env::temp_dir().join(format!("ttview-crop-{}.ppm", std::process::id()));
+    let mut ppm = b"P6 4 2 255\n".to_vec();
+    for _ in 0..2 {
+        ppm.extend([255, 0, 0, 255, 0, 0, 0, 0, 255, 0, 0, 255]);
+    }
+    std::fs::write(&path, ppm).unwrap();
+    let crop = |crop: &str| {
+        Command::new(env!("CARGO_BIN_EXE_ttview"))
+            .args(["--reproducible", "--quiet", "--zoom", "1", "--raw-out", "-"])
+            .args(["--crop", crop])
+            .arg(&path)
+            .output()
+            .unwrap()
+    };
+    let inside = crop("2,0,2,2");
+    let outside = crop("3,1,2,2");
+    std::fs::remove_file(&path).unwrap();
+    assert!(inside.status.success());
+    assert_eq!(inside.stdout, [0, 0, 255].repeat(4));
+    assert!(!outside.status.success());
+    assert!(outside.stdout.is_empty());
+    let error = String::from_utf8(outside.stderr).unwrap();
+    assert!(error.contains("lies outside the 4x2 image"), "{error}");
+}
+
+#[test]
+fn wide_image_in_a_square_box() {
+    // 12x2 pixels, red, green and blue from left to right.
+    let path = std::env::temp_dir().join(format!("ttview-fit-{}.ppm", std::process::id()));
+    let mut ppm = b"P6 12 2 255\n".to_vec();
+    for _ in 0..2 {
+        for color in [[255, 0, 0], [0, 255, 0], [0, 0, 255]] {
+            ppm.extend(color.repeat(4));
+        }
+    }
+    std::fs::write(&path, ppm).unwrap();
+    let fit = |mode: &str| {
+        let output = Command::new(env!("CARGO_BIN_EXE_ttview"))
+            .args(["--reproducible", "--quiet", "--width", "4", "--height", "4"])
+            .arg(format!("--fit={mode}"))
+            .args(["--raw-out", "-"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        output.stdout
+    };
+    // Stretched across the whole box, from red to blue in every row.
+    let stretch = fit("stretch");
+    assert_eq!(stretch.len(), 4 * 4 * 3);
+    for row in stretch.chunks(4 * 3) {
+        assert!(row[0] > 200 && row[2] < 50, "{row:?}");
+        assert!(row[9] < 50 && row[11] > 200, "{row:?}");
+    }
+    // A single row in the middle, with transparent rows above and below.
+    let contain = fit("contain");
+    assert_eq!(contain.len(), 4 * 4 * 4);
+    let opaque: Vec<bool> = contain
+        .chunks(4 * 4)
+        .map(|row| row.chunks(4).all(|pixel| pixel[3] == 255))
+        .collect();
+    assert_eq!(opaque, [false, true, false, false]);
+    let middle = &contain[4 * 4..2 * 4 * 4];
+    assert!(middle[0] > 200 && middle[14] > 200, "{middle:?}");
+    // Only the green middle fills the box.
+    let cover = fit("cover");
+    assert_eq!(cover, [0, 255, 0].repeat(4 * 4));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn sizes_are_validated_and_capped() {
+    let path = fixture("capped");
+    for flag in ["--width", "--height"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_ttview"))
+            .args([flag, "0"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(!output.status.success(), "{flag}");
+        let error = String::from_utf8(output.stderr).unwrap();
+        assert!(error.contains("'0'"), "{error}");
+    }
+    let columns = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_ttview"))
+            .args([
+                "--reproducible",
+                "--quiet",
+                "--width",
+                "20",
+                "--max-width",
+                "10",
+            ])
+            .args(args)
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let error = String::from_utf8(output.stderr).unwrap();
+        let output = String::from_utf8(output.stdout).unwrap();
+        (output.lines().next().unwrap().matches('▀').count(), error)
+    };
+    let (capped, warning) = columns(&[]);
+    assert_eq!(capped, 10);
+    assert!(
+        warning.contains("limiting width to 10 columns"),
+        "{warning}"
+    );
+    let (forced, warning) = columns(&["--force"]);
+    assert_eq!(forced, 20);
+    assert!(warning.is_empty(), "{warning}");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn per_file_overrides_replace_the_defaults() {
+    let path = fixture("overrides");
+    let name = path.to_str().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_ttview"))
+        .args(["--reproducible", "--quiet", "--width", "4"])
+        .arg(format!("{name}:style=braille,width=8"))
+        .arg(name)
+        .output()
+        .unwrap();
+    let malformed = Command::new(env!("CARGO_BIN_EXE_ttview"))
+        .args(["--reproducible", "--quiet", "--width", "4"])
+        .arg(format!("{name}:style=sideways"))
+        .output()
+        .unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert!(output.status.success());
+    let output = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = output.lines().filter(|line| !line.is_empty()).collect();
+    // Two lines of 4 braille cells for 8x8 pixels, then the default 4x4 in half blocks.
+    assert_eq!(lines.len(), 4, "{output}");
+    for line in &lines[..2] {
+        let cells = line
+            .chars()
+            .filter(|c| ('\u{2800}'..='\u{28FF}').contains(c));
+        assert_eq!(cells.count(), 4, "{line:?}");
+    }
+    for line in &lines[2..] {
+        assert_eq!(line.matches('▀').count(), 4, "{line:?}");
+    }
+    assert!(!malformed.status.success());
+    let error = String::from_utf8(malformed.stderr).unwrap();
+    assert!(error.contains("invalid style 'sideways'"), "{error}");
+}
@@ -0,0 +1,83 @@
+//! Reducing the colors of each output line and matching them to the xterm palette.
+
+use image::{Rgb, Rgb32FImage};
+use std::collections::HashSet;
+use ttview::colors::{limit_colors_per_line, to_u8, xterm_index};
+
+/// Hue going around the color wheel from left to right, the same in every row.
+fn rainbow(width: u32, height: u32) -> Rgb32FImage {
+    Rgb32FImage::from_fn(width, height, |x, _| {
+        let hue = x as f32 / width as f32 * 6.0;
+        let channel = |offset: f32| {
+            let distance = ((hue - offset).rem_euclid(6.0) - 3.0).abs();
+            (distance - 1.0).clamp(0.0, 1.0)
+        };
+        Rgb([channel(0.0), channel(2.0), channel(4.0)])
+    })
+}
+
+/// Number of distinct 8 bit colors in each line of `line_height` rows.
+fn colors_per_line(image: &Rgb32FImage, line_height: u32) -> Vec<usize> {
+    (0..image.height())
+        .step_by(line_height as usize)
+        .map(|top| {
+            let rows = top..(top + line_height).min(image.height());
+            rows.flat_map(|y| (0..image.width()).map(move |x| (x, y)))
+                .map(|(x, y)| image.get_pixel(x, y).0.map(to_u8))
+                .collect::<HashSet<_>>()
+                .len()
+        })
+        .collect()
+}
+
+#[test]
+fn rainbow_is_reduced() {
+    let mut image = rainbow(360, 5);
+    assert!(colors_per_line(&image, 2).iter().all(|&count| count > 300));
+    for max_colors in [1, 2, 8, 50] {
+        let mut limited = image.clone();
+        limit_colors_per_line(&mut limited, 2, max_colors);
+        let counts = colors_per_line(&limited, 2);
+        assert_eq!(counts.len(), 3);
+        assert!(
+            counts.iter().all(|&count| count <= max_colors),
+            "{counts:?}"
+        );
+    }
+    // Merged colors stay close to the originals.
+    limit_colors_per_line(&mut image, 2, 50);
+    let original = rainbow(360, 5);
+    for (merged, original) in image.pixels().zip(original.pixels()) {
+        let distance = (0..3)
+            .map(|c| (merged[c] - original[c]).abs())
+            .fold(0.0, f32::max);
+        assert!(distance < 0.1, "{merged:?} for {original:?}");
+    }
+}
+
+#[test]
+fn few_colors_are_kept() {
+    let original = Rgb32FImage::from_fn(8, 2, |x, y| Rgb([x as f32 / 8.0, y as f32, 0.5]));
+    let mut image = original.clone();
+    limit_colors_per_line(&mut image, 2, 16);
+    assert_eq!(image, original);
+}
+
+#[test]
+fn wide_lines_with_many_colors() {
+    // Every pixel of these lines has its own color.
+    let mut image = Rgb32FImage::from_fn(1600, 4, |x, y| {
+        Rgb([x as f32 / 1600.0, (x % 40) as f32 / 40.0, y as f32 / 4.0])
+    });
+    limit_colors_per_line(&mut image, 2, 16);
+    assert!(colors_per_line(&image, 2).iter().all(|&count| count <= 16));
+}
+
+#[test]
+fn xterm_index_of_primary_colors() {
+    assert_eq!(xterm_index([0, 0, 0]), 16);
+    assert_eq!(xterm_index([255, 255, 255]), 231);
+    assert_eq!(xterm_index([255, 0, 0]), 196);
+    // Greys between cube levels come from the ramp.
+    assert_eq!(xterm_index([128, 128, 128]), 244);
+}
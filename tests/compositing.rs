@@ -0,0 +1,72 @@
+//! Blending transparent images onto backgrounds and masking them by color.
+
+use image::{Rgba, Rgba32FImage};
+use ttview::colors::to_u8;
+use ttview::compositing::{Background, composite, mask};
+
+#[test]
+fn tiles_alternate_behind_transparent_pixels() {
+    let background: Background = "2,2,ff0000,0000ff".parse().unwrap();
+    // Transparent except for a white pixel in the bottom right corner.
+    let image = Rgba32FImage::from_fn(6, 4, |x, y| {
+        if (x, y) == (5, 3) {
+            Rgba([1.0; 4])
+        } else {
+            Rgba([0.0; 4])
+        }
+    });
+    let composited = composite(&image, &background);
+    for (x, y, pixel) in composited.enumerate_pixels() {
+        let expected = if (x, y) == (5, 3) {
+            [255, 255, 255]
+        } else if (x / 2 + y / 2) % 2 == 0 {
+            [255, 0, 0]
+        } else {
+            [0, 0, 255]
+        };
+        assert_eq!(pixel.0.map(to_u8), expected, "pixel at {x},{y}");
+    }
+}
+
+#[test]
+fn invalid_tiles_are_rejected() {
+    assert!("0,2,ff0000,0000ff".parse::<Background>().is_err());
+    assert!("2,2,ff0000".parse::<Background>().is_err());
+    assert!("2,2,ff0000,blue".parse::<Background>().is_err());
+}
+
+#[test]
+fn semi_transparent_edge_blends_in_linear_light() {
+    // White fading out over three pixels onto a grey background.
+    let image = Rgba32FImage::from_fn(4, 1, |x, _| Rgba([1.0, 1.0, 1.0, 1.0 - x as f32 / 3.0]));
+    let background = Background::Solid(image::Rgb([0.5; 3]));
+    let composited = composite(&image, &background);
+    let values: Vec<u8> = composited.pixels().map(|pixel| to_u8(pixel[0])).collect();
+    // Mixing values instead of light would give 212 and 170 in between.
+    assert_eq!(values, [255, 223, 183, 128]);
+    assert!(
+        composited
+            .pixels()
+            .all(|pixel| pixel[0] == pixel[1] && pixel[1] == pixel[2])
+    );
+}
+
+#[test]
+fn mask_keeps_only_the_key_color() {
+    // Red, nearly red within the tolerance, orange outside of it and blue.
+    let colors = [[255, 0, 0], [250, 10, 0], [255, 64, 0], [0, 0, 255]];
+    let mut image = Rgba32FImage::from_fn(4, 1, |x, _| {
+        let [r, g, b] = colors[x as usize].map(|c| c as f32 / 255.0);
+        Rgba([r, g, b, 1.0])
+    });
+    mask(&mut image, image::Rgb([1.0, 0.0, 0.0]), 32.0);
+    let alpha: Vec<f32> = image.pixels().map(|pixel| pixel[3]).collect();
+    assert_eq!(alpha, [1.0, 1.0, 0.0, 0.0]);
+    // Only the matching pixels are left to show over the background.
+    let composited = composite(&image, &Background::Solid(image::Rgb([0.0; 3])));
+    let values: Vec<[u8; 3]> = composited
+        .pixels()
+        .map(|pixel| pixel.0.map(to_u8))
+        .collect();
+    assert_eq!(values, [[255, 0, 0], [250, 10, 0], [0, 0, 0], [0, 0, 0]]);
+}
@@ -0,0 +1,93 @@
+//! Ordered and blue noise dithering, which has to give the same output for the same input.
+
+use image::{Rgb, Rgb32FImage};
+use ttview::Style;
+use ttview::colors::to_srgb;
+use ttview::dithering::Dither;
+use ttview::styling::RenderOptions;
+
+/// Horizontal gradient covering every brightness.
+fn gradient() -> Rgb32FImage {
+    Rgb32FImage::from_fn(100, 60, |x, _| Rgb([x as f32 / 99.0; 3]))
+}
+
+fn dither(seed: u64) -> Rgb32FImage {
+    let mut image = gradient();
+    Dither::BlueNoise.apply(&mut image, false, seed);
+    image
+}
+
+#[test]
+fn same_seed_same_output() {
+    assert_eq!(dither(1), dither(1));
+    // A different seed in between must not leave anything behind.
+    let first = dither(5);
+    dither(6);
+    assert_eq!(first, dither(5));
+}
+
+#[test]
+fn different_seed_different_output() {
+    assert_ne!(dither(1), dither(2));
+}
+
+#[test]
+fn preserves_average_brightness() {
+    let image = dither(0);
+    let average = |image: &Rgb32FImage| {
+        image.pixels().map(|pixel| pixel[0]).sum::<f32>() / (image.width() * image.height()) as f32
+    };
+    assert!((average(&image) - average(&gradient())).abs() < 0.01);
+}
+
+/// Pixels set by ordered dithering a flat 4x4 image of the given brightness.
+fn bayer4(value: f32) -> [[u8; 4]; 4] {
+    let mut image = Rgb32FImage::from_pixel(4, 4, Rgb([value; 3]));
+    Dither::Bayer4.apply(&mut image, false, 0);
+    std::array::from_fn(|y| std::array::from_fn(|x| image.get_pixel(x as u32, y as u32)[0] as u8))
+}
+
+#[test]
+fn bayer_pattern_of_flat_grey() {
+    // The Bayer matrix spreads the lit pixels evenly over the tile.
+    assert_eq!(
+        bayer4(0.5),
+        [[1, 0, 1, 0], [0, 1, 0, 1], [1, 0, 1, 0], [0, 1, 0, 1]]
+    );
+    assert_eq!(
+        bayer4(0.25),
+        [[1, 0, 1, 0], [0, 0, 0, 0], [1, 0, 1, 0], [0, 0, 0, 0]]
+    );
+}
+
+/// Fraction of the drawn area lit by the dithered style for a flat image.
+fn dithered_coverage(value: f32) -> f32 {
+    let mut image = Rgb32FImage::from_pixel(64, 64, Rgb([value; 3]));
+    let raster = Style::Dithered.rasterize(&mut image, &RenderOptions::default());
+    let lit = raster.pixels().filter(|pixel| pixel[0] > 127).count();
+    lit as f32 / raster.pixels().len() as f32
+}
+
+#[test]
+fn dithered_coverage_matches_the_light() {
+    // Half the light of white is lit half the time, where dithering the gamma encoded
+    // value would light 73%.
+    let half = dithered_coverage(to_srgb(0.5));
+    assert!((half - 0.5).abs() < 0.02, "{half}");
+    // Likewise 50% grey in sRGB only emits 21% of the light of white.
+    let grey = dithered_coverage(0.5);
+    assert!((grey - 0.214).abs() < 0.02, "{grey}");
+}
+
+#[test]
+fn serpentine_scans_odd_rows_right_to_left() {
+    let second_row = |serpentine| {
+        // Black above a row of dark grey, so only the second row has errors to spread.
+        let mut image = Rgb32FImage::from_fn(4, 2, |_, y| Rgb([y as f32 * 0.4; 3]));
+        Dither::FloydSteinberg.apply(&mut image, serpentine, 0);
+        (0..4).map(|x| image.get_pixel(x, 1)[0]).collect::<Vec<_>>()
+    };
+    // The error of the first pixel scanned lights its neighbor in the scan direction.
+    assert_eq!(second_row(false), [0.0, 1.0, 0.0, 0.0]);
+    assert_eq!(second_row(true), [0.0, 0.0, 1.0, 0.0]);
+}
@@ -0,0 +1,205 @@
+//! Renders small synthetic images in every deterministic style, filter and dither algorithm
+//! and compares the exact output with the files in `tests/golden`.
+//! Run with `UPDATE_GOLDEN=1` to write the current output instead, after checking that
+//! changes to it are intended.
+
+use image::{DynamicImage, Rgb, RgbImage};
+use std::path::Path;
+use ttview::colors::parse_hex;
+use ttview::dithering::Dither;
+use ttview::styling::{ASCII_GRADIENT, Glyph, RenderOptions, STRIP_ALIGN};
+use ttview::{DEFAULT_CELL_ASPECT, Filter, Style, resizing};
+
+/// Width images are rendered at, in pixels.
+const WIDTH: u32 = 12;
+
+fn fixtures() -> [(&'static str, DynamicImage); 3] {
+    let solid = RgbImage::from_pixel(24, 24, Rgb([200, 50, 50]));
+    // Brightness increases to the right, with a different hue in each third from the top.
+    let gradient = RgbImage::from_fn(24, 24, |x, y| {
+        let value = (x * 255 / 23) as u8;
+        match y / 8 {
+            0 => Rgb([value, value, value]),
+            1 => Rgb([value, value / 2, 0]),
+            _ => Rgb([0, value / 2, value]),
+        }
+    });
+    let checkerboard = RgbImage::from_fn(24, 24, |x, y| {
+        if (x / 4 + y / 4) % 2 == 0 {
+            Rgb([0, 0, 0])
+        } else {
+            Rgb([255, 255, 255])
+        }
+    });
+    [
+        ("solid", DynamicImage::ImageRgb8(solid)),
+        ("gradient", DynamicImage::ImageRgb8(gradient)),
+        ("checkerboard", DynamicImage::ImageRgb8(checkerboard)),
+    ]
+}
+
+fn styles() -> Vec<Style> {
+    let heatmap = ["0000ff", "ffffff", "ff0000"].map(|color| parse_hex(color).unwrap());
+    vec![
+        Style::Color,
+        Style::Greyscale,
+        Style::Gradient(ASCII_GRADIENT.chars().collect()),
+        Style::ColorGradient(heatmap.to_vec()),
+        Style::Braille,
+        Style::DitheredBraille,
+        Style::Dithered,
+        Style::Subpixel,
+        Style::BrailleEdges,
+        Style::BayerColor,
+        Style::ColorBraille,
+        Style::Quadrant,
+        Style::Sextant,
+        Style::Mono,
+        Style::Compact,
+    ]
+}
+
+const FILTERS: [Filter; 6] = [
+    Filter::Nearest,
+    Filter::Triangle,
+    Filter::CatmullRom,
+    Filter::Gaussian,
+    Filter::Lanczos3,
+    Filter::Mitchell,
+];
+
+const GLYPHS: [Glyph; 4] = [
+    Glyph::UpperHalf,
+    Glyph::LowerHalf,
+    Glyph::FullBlock,
+    Glyph::SpaceBg,
+];
+
+const DITHERS: [Dither; 9] = [
+    Dither::FloydSteinberg,
+    Dither::Atkinson,
+    Dither::JarvisJudiceNinke,
+    Dither::Sierra,
+    Dither::Stucki,
+    Dither::Bayer2,
+    Dither::Bayer4,
+    Dither::Bayer8,
+    Dither::BlueNoise,
+];
+
+fn render(image: &DynamicImage, style: &Style, filter: Filter, options: &RenderOptions) -> String {
+    let pixel_aspect = style.pixel_aspect(DEFAULT_CELL_ASPECT);
+    let resized = resizing::resize(image.clone(), (Some(WIDTH), None), filter, pixel_aspect);
+    style.apply_to_string(&mut resized.to_rgb32f(), options)
+}
+
+#[test]
+fn compact_rows_match_half_blocks() {
+    let defaults = RenderOptions::default();
+    for (fixture, image) in fixtures() {
+        let half_blocks = render(&image, &Style::Color, Filter::default(), &defaults);
+        let compact = render(&image, &Style::Compact, Filter::default(), &defaults);
+        assert_eq!(
+            compact.lines().count(),
+            half_blocks.lines().count(),
+            "{fixture}"
+        );
+    }
+}
+
+#[test]
+fn strips_match_whole_image() {
+    for (fixture, image) in fixtures() {
+        for style in styles() {
+            // Only the options that change how pixels depend on each other are varied.
+            let options: Vec<RenderOptions> = match style {
+                Style::Dithered | Style::DitheredBraille => DITHERS
+                    .map(|dither| RenderOptions {
+                        dither,
+                        ..RenderOptions::default()
+                    })
+                    .into(),
+                Style::Braille => vec![RenderOptions {
+                    braille_gradient: true,
+                    ..RenderOptions::default()
+                }],
+                _ => vec![RenderOptions::default()],
+            };
+            // Tall enough for several strips, the last of them cut short.
+            let pixel_aspect = style.pixel_aspect(DEFAULT_CELL_ASPECT);
+            let resized = resizing::resize(
+                image.clone(),
+                (Some(2 * STRIP_ALIGN + 10), None),
+                Filter::default(),
+                pixel_aspect,
+            );
+            for options in &options {
+                let whole = style.apply_to_string(&mut resized.to_rgb32f(), options);
+                let mut strips = Vec::new();
+                style
+                    .apply_in_strips(&mut resized.to_rgb32f(), options, STRIP_ALIGN, &mut strips)
+                    .unwrap();
+                assert!(
+                    whole.as_bytes() == strips,
+                    "{fixture}-{style} with {:?}",
+                    options.dither
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn golden() {
+    let defaults = RenderOptions::default();
+    let mut cases = Vec::new();
+    for (fixture, image) in fixtures() {
+        for style in styles() {
+            let output = render(&image, &style, Filter::default(), &defaults);
+            cases.push((format!("{fixture}-{style}"), output));
+        }
+    }
+    let (_, gradient) = &fixtures()[1];
+    for filter in FILTERS {
+        let name = format!("gradient-color-{filter:?}").to_lowercase();
+        cases.push((name, render(gradient, &Style::Color, filter, &defaults)));
+    }
+    for dither in DITHERS {
+        let options = RenderOptions {
+            dither,
+            ..RenderOptions::default()
+        };
+        let name = format!("gradient-dithered-{dither:?}").to_lowercase();
+        let output = render(gradient, &Style::Dithered, Filter::default(), &options);
+        cases.push((name, output));
+    }
+    for glyph in GLYPHS {
+        let options = RenderOptions {
+            glyph,
+            ..RenderOptions::default()
+        };
+        let name = format!("gradient-color-{glyph:?}").to_lowercase();
+        cases.push((
+            name,
+            render(gradient, &Style::Color, Filter::default(), &options),
+        ));
+    }
+
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some_and(|value| value == "1");
+    let mut mismatches = Vec::new();
+    for (name, output) in cases {
+        let path = directory.join(format!("{name}.txt"));
+        if update {
+            std::fs::create_dir_all(&directory).unwrap();
+            std::fs::write(&path, output).unwrap();
+        } else if std::fs::read_to_string(&path).ok().as_deref() != Some(output.as_str()) {
+            mismatches.push(name);
+        }
+    }
+    assert!(
+        mismatches.is_empty(),
+        "output differs from tests/golden for {}, run with UPDATE_GOLDEN=1 to update",
+        mismatches.join(", ")
+    );
+}
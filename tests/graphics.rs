@@ -0,0 +1,73 @@
+//! Escape sequences of the graphics protocols.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use image::{ColorType, GrayImage, Luma, Rgb, Rgb32FImage};
+use ttview::graphics::{kitty, sixel, tmux_passthrough};
+
+/// Red 4x6 image with a transparent left half, when given the alpha channel.
+fn half_transparent() -> (Rgb32FImage, GrayImage) {
+    let image = Rgb32FImage::from_pixel(4, 6, Rgb([1.0, 0.0, 0.0]));
+    let alpha = GrayImage::from_fn(4, 6, |x, _| Luma([if x < 2 { 0 } else { 255 }]));
+    (image, alpha)
+}
+
+/// PNG sent in a kitty escape sequence, decoded.
+fn kitty_png(output: &str) -> image::DynamicImage {
+    let payload: String = output
+        .split("\x1B_G")
+        .filter_map(|sequence| sequence.split_once(';'))
+        .map(|(_, data)| data.trim_end_matches("\x1B\\"))
+        .collect();
+    let png = STANDARD.decode(payload).unwrap();
+    image::load_from_memory(&png).unwrap()
+}
+
+#[test]
+fn kitty_keeps_alpha() {
+    let (image, alpha) = half_transparent();
+    let png = kitty_png(&kitty(&image, Some(&alpha), (4, 3)));
+    assert_eq!(png.color(), ColorType::Rgba8);
+    let png = png.to_rgba8();
+    assert_eq!(png.get_pixel(0, 0).0, [255, 0, 0, 0]);
+    assert_eq!(png.get_pixel(3, 5).0, [255, 0, 0, 255]);
+}
+
+#[test]
+fn kitty_without_alpha_is_opaque() {
+    let (image, _) = half_transparent();
+    let png = kitty_png(&kitty(&image, None, (4, 3)));
+    assert_eq!(png.color(), ColorType::Rgb8);
+}
+
+#[test]
+fn sixel_leaves_out_transparent_pixels() {
+    let (image, alpha) = half_transparent();
+    let opaque = sixel(&image, None);
+    assert!(opaque.starts_with("\x1BPq\"1;1;4;6"), "{opaque:?}");
+    // A single color in all six rows of the band.
+    assert!(opaque.ends_with("!4~-\x1B\\"), "{opaque:?}");
+    let transparent = sixel(&image, Some(&alpha));
+    assert!(
+        transparent.starts_with("\x1BP0;1q\"1;1;4;6"),
+        "{transparent:?}"
+    );
+    // No rows of the left two columns are set.
+    assert!(transparent.ends_with("??~~-\x1B\\"), "{transparent:?}");
+}
+
+#[test]
+fn tmux_passthrough_wraps_every_sequence() {
+    let output = "\x1B_Ga=T,m=1;AAAA\x1B\\\x1B_Gm=0;BB\x1B\\";
+    assert_eq!(
+        tmux_passthrough(output),
+        "\x1BPtmux;\x1B\x1B_Ga=T,m=1;AAAA\x1B\x1B\\\x1B\\\
+         \x1BPtmux;\x1B\x1B_Gm=0;BB\x1B\x1B\\\x1B\\"
+    );
+    // Sixel images are a single sequence.
+    let (image, _) = half_transparent();
+    let wrapped = tmux_passthrough(&sixel(&image, None));
+    assert_eq!(wrapped.matches("\x1BPtmux;").count(), 1);
+    assert!(wrapped.starts_with("\x1BPtmux;\x1B\x1BP"), "{wrapped:?}");
+    assert!(wrapped.ends_with("\x1B\x1B\\\x1B\\"), "{wrapped:?}");
+}
@@ -0,0 +1,53 @@
+//! Half block cells of two pixel rows each, including a dangling last row.
+
+use image::{Rgb, Rgb32FImage};
+use ttview::Style;
+use ttview::styling::{Glyph, RenderOptions};
+
+/// Red, green and blue pixels on top of each other.
+fn column() -> Rgb32FImage {
+    let colors = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    Rgb32FImage::from_fn(1, 3, |_, y| Rgb(colors[y as usize]))
+}
+
+#[test]
+fn odd_height_fills_the_last_row_with_the_background() {
+    let options = RenderOptions {
+        background: Rgb([0.5; 3]),
+        ..RenderOptions::default()
+    };
+    let output = Style::Color.apply_to_string(&mut column(), &options);
+    assert_eq!(
+        output,
+        "\x1B[38;2;255;0;0;48;2;0;255;0m▀\x1B[0m\n\x1B[38;2;0;0;255;48;2;128;128;128m▀\x1B[0m\n"
+    );
+    let options = RenderOptions {
+        glyph: Glyph::LowerHalf,
+        ..options
+    };
+    let output = Style::Color.apply_to_string(&mut column(), &options);
+    let last = output.lines().nth(1).unwrap();
+    assert_eq!(last, "\x1B[38;2;128;128;128;48;2;0;0;255m▄\x1B[0m");
+}
+
+#[test]
+fn solid_row_has_a_single_color_sequence() {
+    let mut image = Rgb32FImage::from_pixel(8, 2, Rgb([0.0, 0.5, 1.0]));
+    let output = Style::Color.apply_to_string(&mut image, &RenderOptions::default());
+    // One sequence sets both colors for the whole row and one resets them at its end.
+    assert_eq!(output.matches("\x1B[").count(), 2, "{output:?}");
+    assert_eq!(
+        output,
+        "\x1B[38;2;0;128;255;48;2;0;128;255m▀▀▀▀▀▀▀▀\x1B[0m\n"
+    );
+}
+
+#[test]
+fn solid_image_has_a_color_sequence_per_line() {
+    let mut image = Rgb32FImage::from_pixel(40, 20, Rgb([0.25; 3]));
+    let output = Style::Color.apply_to_string(&mut image, &RenderOptions::default());
+    // 400 cells, but only the colors at the start of each of the 10 lines and their resets.
+    assert_eq!(output.matches("▀").count(), 400);
+    assert_eq!(output.matches("\x1B[").count(), 20, "{output:?}");
+    assert!(output.lines().all(|line| line.ends_with("▀\x1B[0m")));
+}
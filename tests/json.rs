@@ -0,0 +1,52 @@
+//! Cell output as JSON, only built with the `json` feature.
+#![cfg(feature = "json")]
+
+use image::{Rgb, Rgb32FImage};
+use ttview::Style;
+use ttview::styling::{Glyph, RenderOptions};
+
+fn json(style: &Style, options: &RenderOptions) -> String {
+    // Red above blue, a single cell for styles with two pixel rows per cell.
+    let mut image = Rgb32FImage::from_fn(1, 2, |_, y| {
+        if y == 0 {
+            Rgb([1.0, 0.0, 0.0])
+        } else {
+            Rgb([0.0, 0.0, 1.0])
+        }
+    });
+    let mut output = Vec::new();
+    style.apply_json(&mut image, options, &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn single_cell() {
+    let output = json(&Style::Color, &RenderOptions::default());
+    assert_eq!(
+        output,
+        "[\n  [{\"glyph\": \"▀\", \"fg\": [255, 0, 0], \"bg\": [0, 0, 255]}]\n]"
+    );
+}
+
+#[test]
+fn default_colors_are_null() {
+    let options = RenderOptions {
+        glyph: Glyph::SpaceBg,
+        ..RenderOptions::default()
+    };
+    let output = json(&Style::Color, &options);
+    assert_eq!(
+        output,
+        "[\n  [{\"glyph\": \" \", \"fg\": null, \"bg\": [128, 0, 128]}]\n]"
+    );
+}
+
+#[test]
+fn line_per_row() {
+    let output = json(&Style::Compact, &RenderOptions::default());
+    assert_eq!(
+        output,
+        "[\n  [{\"glyph\": \" \", \"fg\": null, \"bg\": [255, 0, 0]}],\n  \
+         [{\"glyph\": \" \", \"fg\": null, \"bg\": [0, 0, 255]}]\n]"
+    );
+}
@@ -0,0 +1,124 @@
+//! The library entry points for rendering images as text.
+
+use image::{DynamicImage, Rgb, RgbImage};
+use ttview::{Filter, Style, render, rendered_size};
+
+/// Photo of 100x50 pixels.
+fn photo() -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_fn(100, 50, |x, y| {
+        Rgb([x as u8 * 2, y as u8 * 5, 128])
+    }))
+}
+
+/// Columns and rows of rendered text.
+fn measure(output: &str) -> (u32, u32) {
+    let columns = output
+        .lines()
+        .map(|line| line.chars().filter(|c| !c.is_ascii()).count())
+        .max()
+        .unwrap_or(0);
+    (columns as u32, output.lines().count() as u32)
+}
+
+#[test]
+fn half_block_rows_are_halved() {
+    // Two square pixels per cell of a typical terminal font.
+    let size = rendered_size((100, 50), &Style::Color, (Some(40), None));
+    assert_eq!(size, (40, 10));
+    let output = render(&photo(), &Style::Color, Filter::default(), (Some(40), None));
+    assert_eq!(measure(&output), size);
+}
+
+#[test]
+fn braille_cells_are_quartered() {
+    let size = rendered_size((100, 50), &Style::Braille, (Some(40), None));
+    assert_eq!(size, (20, 5));
+    let output = render(
+        &photo(),
+        &Style::Braille,
+        Filter::default(),
+        (Some(40), None),
+    );
+    assert_eq!(measure(&output), size);
+}
+
+#[test]
+fn default_width() {
+    assert_eq!(
+        rendered_size((100, 50), &Style::Color, (None, None)),
+        (80, 20)
+    );
+    assert_eq!(
+        rendered_size((100, 50), &Style::Braille, (None, Some(12))),
+        (12, 3)
+    );
+}
+
+#[test]
+fn auto_renders_like_color() {
+    let image = photo();
+    let dims = (Some(40), None);
+    assert_eq!(
+        render(&image, &Style::Auto, Filter::default(), dims),
+        render(&image, &Style::Color, Filter::default(), dims)
+    );
+    assert_eq!(
+        rendered_size((100, 50), &Style::Auto, dims),
+        rendered_size((100, 50), &Style::Color, dims)
+    );
+}
+
+#[test]
+fn cells_of_a_square_image_per_style() {
+    let gradient = Style::Gradient(" .:#".chars().collect());
+    let color_gradient = Style::ColorGradient(vec![Rgb([0.0; 3]), Rgb([1.0; 3])]);
+    // 40 pixels across and as high on screen, in cells twice as high as wide.
+    let cases = [
+        (Style::Color, (40, 20)),
+        (Style::Greyscale, (40, 20)),
+        (gradient, (40, 20)),
+        (color_gradient, (40, 20)),
+        (Style::Braille, (20, 10)),
+        (Style::DitheredBraille, (20, 10)),
+        (Style::Dithered, (40, 20)),
+        (Style::Subpixel, (40, 20)),
+        (Style::BrailleEdges, (20, 10)),
+        (Style::BayerColor, (40, 20)),
+        (Style::ColorBraille, (20, 10)),
+        (Style::Quadrant, (20, 10)),
+        (Style::Sextant, (20, 10)),
+        (Style::Mono, (80, 40)),
+        (Style::Compact, (40, 20)),
+        (Style::Auto, (40, 20)),
+    ];
+    for (style, cells) in cases {
+        let size = rendered_size((100, 100), &style, (Some(40), None));
+        assert_eq!(size, cells, "{style:?}");
+    }
+}
+
+#[test]
+fn height_alone_corrects_the_width() {
+    // Quadrant pixels are half as wide as high, so 20 pixels of height need 40 across.
+    assert_eq!(
+        rendered_size((100, 100), &Style::Quadrant, (None, Some(20))),
+        (20, 10)
+    );
+    assert_eq!(
+        rendered_size((100, 100), &Style::Compact, (None, Some(20))),
+        (40, 20)
+    );
+}
+
+#[test]
+fn both_dimensions_are_not_corrected() {
+    // The box is taken as it is, even where that stretches the image.
+    assert_eq!(
+        rendered_size((100, 100), &Style::Quadrant, (Some(40), Some(40))),
+        (20, 20)
+    );
+    assert_eq!(
+        rendered_size((100, 100), &Style::Color, (Some(40), Some(40))),
+        (40, 20)
+    );
+}
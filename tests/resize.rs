@@ -0,0 +1,42 @@
+//! Fitting images again when the terminal is resized, as animations do between frames.
+
+use std::cell::Cell;
+use ttview::resizing::fit;
+use ttview::terminal::SizeWatch;
+
+#[test]
+fn reports_only_changes() {
+    let size = Cell::new(Some((80, 24)));
+    let mut watch = SizeWatch::new(|| size.get());
+    assert_eq!(watch.changed(), None);
+    size.set(Some((120, 40)));
+    assert_eq!(watch.changed(), Some((120, 40)));
+    assert_eq!(watch.changed(), None);
+    // Unknown sizes keep the last one, so returning to it is no change.
+    size.set(None);
+    assert_eq!(watch.changed(), None);
+    size.set(Some((120, 40)));
+    assert_eq!(watch.changed(), None);
+}
+
+#[test]
+fn starts_without_size() {
+    let size = Cell::new(None);
+    let mut watch = SizeWatch::new(|| size.get());
+    size.set(Some((80, 24)));
+    assert_eq!(watch.changed(), Some((80, 24)));
+}
+
+#[test]
+fn refits_to_new_size() {
+    let original = (400, 200);
+    let size = Cell::new(Some((80, 24)));
+    let mut watch = SizeWatch::new(|| size.get());
+    // Two pixel rows per terminal row, as in the color style.
+    let target = |(columns, rows): (u32, u32)| fit(original, (columns, rows * 2), 1.0);
+    let before = target(size.get().unwrap());
+    size.set(Some((40, 24)));
+    let after = watch.changed().map(target).expect("size changed");
+    assert_eq!(before, (Some(80), None));
+    assert_eq!(after, (Some(40), None));
+}
@@ -0,0 +1,117 @@
+//! Resizing filters and the dimensions images are resized to.
+
+use image::{DynamicImage, Rgb, Rgb32FImage, Rgba};
+use ttview::{Filter, resizing};
+
+/// Dark grey on the left and light grey on the right, away from black and white so that
+/// overshoot is not clipped.
+fn step_edge() -> DynamicImage {
+    let image = Rgb32FImage::from_fn(64, 4, |x, _| {
+        if x < 29 {
+            Rgb([0.25; 3])
+        } else {
+            Rgb([0.75; 3])
+        }
+    });
+    DynamicImage::ImageRgb32F(image)
+}
+
+/// How far the resized edge goes beyond the values of the original on either side.
+fn overshoot(filter: Filter) -> f32 {
+    let resized = resizing::resize(step_edge(), (Some(24), Some(4)), filter, 1.0);
+    resized
+        .to_rgb32f()
+        .pixels()
+        .flat_map(|pixel| pixel.0)
+        .map(|value| (0.25 - value).max(value - 0.75))
+        .fold(0.0, f32::max)
+}
+
+#[test]
+fn mitchell_rings_less_than_lanczos() {
+    let lanczos = overshoot(Filter::Lanczos3);
+    let mitchell = overshoot(Filter::Mitchell);
+    assert!(lanczos > 0.01, "lanczos overshoot {lanczos}");
+    assert!(mitchell < lanczos / 2.0, "mitchell overshoot {mitchell}");
+}
+
+#[test]
+fn sample_point_picks_centers() {
+    // Every pixel holds its own coordinates, with values above 1 as in HDR images.
+    let image = Rgb32FImage::from_fn(8, 4, |x, y| Rgb([x as f32, y as f32, 2.0]));
+    let sampled = resizing::sample_point(&DynamicImage::ImageRgb32F(image), (Some(4), None), 1.0);
+    let sampled = sampled.to_rgba32f();
+    assert_eq!(sampled.dimensions(), (4, 2));
+    for (x, y, pixel) in sampled.enumerate_pixels() {
+        let expected = [(2 * x + 1) as f32, (2 * y + 1) as f32, 2.0, 1.0];
+        assert_eq!(*pixel, Rgba(expected), "pixel at {x},{y}");
+    }
+}
+
+#[test]
+fn thin_images_keep_a_pixel() {
+    assert_eq!(
+        resizing::target_dimensions((1000, 1), (Some(10), None), 0.5),
+        (10, 1)
+    );
+    assert_eq!(
+        resizing::target_dimensions((1, 1000), (None, Some(10)), 2.0),
+        (1, 10)
+    );
+}
+
+#[test]
+fn integer_scale_triples_a_sprite() {
+    // A 16 pixel sprite in 50 columns of half blocks, whose pixels are square.
+    let dim = resizing::integer_scale((16, 16), (Some(50), None), 1.0);
+    assert_eq!(dim, (Some(48), Some(48)));
+    let sprite = image::RgbImage::from_fn(16, 16, |x, y| Rgb([x as u8 * 16, y as u8 * 16, 0]));
+    let scaled = resizing::resize(
+        DynamicImage::ImageRgb8(sprite.clone()),
+        dim,
+        Filter::Nearest,
+        1.0,
+    );
+    let scaled = scaled.to_rgb8();
+    for (x, y, pixel) in scaled.enumerate_pixels() {
+        assert_eq!(pixel, sprite.get_pixel(x / 3, y / 3), "pixel at {x},{y}");
+    }
+    // Too small for a whole factor, the requested size is kept.
+    assert_eq!(
+        resizing::integer_scale((16, 16), (Some(10), None), 1.0),
+        (Some(10), None)
+    );
+}
+
+#[test]
+fn pixelate_averages_blocks() {
+    // A gradient in both directions, with a partial block at the bottom.
+    let image = Rgb32FImage::from_fn(8, 6, |x, y| Rgb([x as f32 / 8.0, y as f32 / 6.0, 0.5]));
+    let pixelated = resizing::pixelate(&DynamicImage::ImageRgb32F(image), 4).to_rgba32f();
+    assert_eq!(pixelated.dimensions(), (8, 6));
+    for (x, y, pixel) in pixelated.enumerate_pixels() {
+        // Averages of x in 0..4 or 4..8 and of y in 0..4 or 4..6.
+        let red = if x < 4 { 1.5 } else { 5.5 } / 8.0;
+        let green = if y < 4 { 1.5 } else { 4.5 } / 6.0;
+        let expected = [red, green, 0.5, 1.0];
+        for c in 0..4 {
+            assert!((pixel[c] - expected[c]).abs() < 1e-6, "pixel at {x},{y}");
+        }
+    }
+}
+
+#[test]
+fn zoom_scales_both_dimensions() {
+    assert_eq!(resizing::zoom((100, 50), 0.5), (Some(50), Some(25)));
+    assert_eq!(resizing::zoom((100, 50), 2.0), (Some(200), Some(100)));
+    // Tiny factors still keep a pixel.
+    assert_eq!(resizing::zoom((100, 50), 0.001), (Some(1), Some(1)));
+    let image = DynamicImage::ImageRgb32F(Rgb32FImage::new(100, 50));
+    let zoomed = resizing::resize(
+        image,
+        resizing::zoom((100, 50), 0.5),
+        Filter::default(),
+        1.0,
+    );
+    assert_eq!((zoomed.width(), zoomed.height()), (50, 25));
+}
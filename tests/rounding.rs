@@ -0,0 +1,39 @@
+//! Conversion of channel values to the 8 bit colors written to the terminal.
+
+use image::{Rgb, Rgb32FImage};
+use ttview::Style;
+use ttview::colors::to_u8;
+use ttview::styling::RenderOptions;
+
+#[test]
+fn rounds_to_nearest() {
+    assert_eq!(to_u8(0.0), 0);
+    assert_eq!(to_u8(0.999), 255);
+    assert_eq!(to_u8(1.0), 255);
+    assert_eq!(to_u8(0.5), 128);
+    assert_eq!(to_u8(1.0 / 255.0), 1);
+}
+
+#[test]
+fn clamps_out_of_range() {
+    assert_eq!(to_u8(1.5), 255);
+    assert_eq!(to_u8(-0.5), 0);
+    assert_eq!(to_u8(f32::INFINITY), 255);
+}
+
+#[test]
+fn encodes_boundary_values() {
+    // Top pixel almost white, bottom pixel brighter than white as after a brightness boost.
+    let mut image = Rgb32FImage::from_fn(1, 2, |_, y| {
+        if y == 0 {
+            Rgb([0.999; 3])
+        } else {
+            Rgb([1.5; 3])
+        }
+    });
+    let output = Style::Color.apply_to_string(&mut image, &RenderOptions::default());
+    assert!(
+        output.starts_with("\x1B[38;2;255;255;255;48;2;255;255;255m"),
+        "{output:?}"
+    );
+}
@@ -0,0 +1,181 @@
+//! Cells drawn by the styles for small images of known brightness.
+
+use image::{Rgb, Rgb32FImage};
+use ttview::Style;
+use ttview::colors::Luma;
+use ttview::styling::{RenderOptions, sobel};
+
+/// Image of a single brightness, which may be outside [0, 1] like HDR sources.
+fn flat(width: u32, height: u32, value: f32) -> Rgb32FImage {
+    Rgb32FImage::from_pixel(width, height, Rgb([value; 3]))
+}
+
+fn gradient() -> Style {
+    Style::Gradient(" .:#".chars().collect())
+}
+
+#[test]
+fn white_is_the_last_gradient_character() {
+    let output = gradient().apply_to_string(&mut flat(2, 2, 1.0), &RenderOptions::default());
+    assert_eq!(output, "##\n");
+}
+
+#[test]
+fn brighter_than_white_is_clamped_to_the_gradient() {
+    let output = gradient().apply_to_string(&mut flat(2, 2, 4.0), &RenderOptions::default());
+    assert_eq!(output, "##\n");
+}
+
+#[test]
+fn empty_gradient_draws_blank_cells() {
+    let output = Style::Gradient(Vec::new())
+        .apply_to_string(&mut flat(2, 2, 0.5), &RenderOptions::default());
+    assert_eq!(output, "  \n");
+}
+
+#[test]
+fn subpixel_fills_a_dangling_row_with_the_background() {
+    let options = RenderOptions {
+        background: Rgb([1.0; 3]),
+        ..RenderOptions::default()
+    };
+    let output = Style::Subpixel.apply_to_string(&mut flat(1, 1, 0.0), &options);
+    assert_eq!(output, "\x1B[38;2;255;255;255m▄\x1B[0m\n");
+}
+
+#[test]
+fn braille_gradient_lights_more_dots_for_darker_cells() {
+    let options = RenderOptions {
+        braille_gradient: true,
+        ..RenderOptions::default()
+    };
+    // Dots are ink, so they are lit for dark pixels like with a single threshold.
+    let dots: Vec<u32> = (0..100)
+        .map(|i| {
+            let output = Style::Braille.apply_to_string(&mut flat(2, 4, i as f32 / 99.0), &options);
+            let cell = output.chars().next().unwrap();
+            (cell as u32 - 0x2800).count_ones()
+        })
+        .collect();
+    assert!(dots.windows(2).all(|pair| pair[0] >= pair[1]), "{dots:?}");
+    // Every number of dots shows up along the way.
+    assert!((0..=8).all(|count| dots.contains(&count)), "{dots:?}");
+}
+
+/// Image made of rows of colors.
+fn pixels(rows: &[&[[f32; 3]]]) -> Rgb32FImage {
+    Rgb32FImage::from_fn(rows[0].len() as u32, rows.len() as u32, |x, y| {
+        Rgb(rows[y as usize][x as usize])
+    })
+}
+
+const RED: [f32; 3] = [1.0, 0.0, 0.0];
+const BLUE: [f32; 3] = [0.0, 0.0, 1.0];
+
+#[test]
+fn quadrant_fills_half_a_cell() {
+    let options = RenderOptions::default();
+    let mut image = pixels(&[&[RED, BLUE], &[RED, BLUE]]);
+    let output = Style::Quadrant.apply_to_string(&mut image, &options);
+    assert_eq!(output, "\x1B[38;2;255;0;0;48;2;0;0;255m▌\x1B[0m\n");
+    let mut image = pixels(&[&[BLUE, BLUE], &[RED, RED]]);
+    let output = Style::Quadrant.apply_to_string(&mut image, &options);
+    assert_eq!(output, "\x1B[38;2;0;0;255;48;2;255;0;0m▀\x1B[0m\n");
+    // A single color fills the whole cell.
+    let output = Style::Quadrant.apply_to_string(&mut flat(2, 2, 1.0), &options);
+    assert_eq!(output, "\x1B[38;2;255;255;255m█\x1B[0m\n");
+}
+
+#[test]
+fn sextant_fills_uniform_and_half_cells() {
+    let options = RenderOptions::default();
+    let output = Style::Sextant.apply_to_string(&mut flat(2, 3, 1.0), &options);
+    assert_eq!(output, "\x1B[38;2;255;255;255m█\x1B[0m\n");
+    let output = Style::Sextant.apply_to_string(&mut flat(2, 3, 0.0), &options);
+    assert_eq!(output, "\x1B[38;2;0;0;0m█\x1B[0m\n");
+    let mut image = pixels(&[&[RED, BLUE], &[RED, BLUE], &[RED, BLUE]]);
+    let output = Style::Sextant.apply_to_string(&mut image, &options);
+    assert_eq!(output, "\x1B[38;2;255;0;0;48;2;0;0;255m▌\x1B[0m\n");
+}
+
+#[test]
+fn color_braille_draws_dots_in_their_color() {
+    let options = RenderOptions {
+        threshold: 0.25,
+        ..RenderOptions::default()
+    };
+    // Red on top of black, only the red dots are lit.
+    let black = [0.0; 3];
+    let mut image = pixels(&[&[RED, RED], &[RED, RED], &[black, black], &[black, black]]);
+    let output = Style::ColorBraille.apply_to_string(&mut image, &options);
+    assert_eq!(output, "\x1B[38;2;255;0;0m⠛\x1B[0m\n");
+}
+
+#[test]
+fn braille_threshold_decides_the_dots_of_grey() {
+    let dots = |threshold| {
+        let options = RenderOptions {
+            threshold,
+            ..RenderOptions::default()
+        };
+        let output = Style::Braille.apply_to_string(&mut flat(4, 4, 0.5), &options);
+        output
+            .chars()
+            .filter(|c| ('\u{2800}'..='\u{28FF}').contains(c))
+            .map(|c| (c as u32 - 0x2800).count_ones())
+            .sum::<u32>()
+    };
+    // Dots are lit for pixels darker than the threshold.
+    assert_eq!(dots(0.3), 0);
+    assert_eq!(dots(0.7), 16);
+}
+
+#[test]
+fn color_gradient_ends_with_its_first_and_last_colors() {
+    let heatmap = Style::ColorGradient(vec![Rgb(BLUE), Rgb([1.0; 3]), Rgb(RED)]);
+    let options = RenderOptions::default();
+    let output = heatmap.apply_to_string(&mut flat(1, 2, 0.0), &options);
+    assert_eq!(output, "\x1B[38;2;0;0;255m█\x1B[0m\n");
+    let output = heatmap.apply_to_string(&mut flat(1, 2, 1.0), &options);
+    assert_eq!(output, "\x1B[38;2;255;0;0m█\x1B[0m\n");
+    let output = Style::ColorGradient(Vec::new()).apply_to_string(&mut flat(1, 2, 0.5), &options);
+    assert_eq!(output, " \n");
+}
+
+#[test]
+fn sobel_finds_a_sharp_edge() {
+    // Black on the left half and white on the right.
+    let image = Rgb32FImage::from_fn(6, 3, |x, _| Rgb([if x < 3 { 0.0 } else { 1.0 }; 3]));
+    let edges = sobel(&image, &RenderOptions::default());
+    for (x, y, pixel) in edges.enumerate_pixels() {
+        // Both sides of a straight edge, at 1/√2 of the strongest, diagonal one.
+        let expected = if x == 2 || x == 3 { 0.5f32.sqrt() } else { 0.0 };
+        assert!(
+            (pixel[0] - expected).abs() < 1e-4,
+            "{} at {x},{y}",
+            pixel[0]
+        );
+    }
+}
+
+#[test]
+fn green_is_brighter_in_rec709() {
+    let grey = |luma| {
+        let options = RenderOptions {
+            luma,
+            linear: false,
+            ..RenderOptions::default()
+        };
+        let mut image = Rgb32FImage::from_pixel(1, 2, Rgb([0.0, 1.0, 0.0]));
+        Style::Greyscale.apply_to_string(&mut image, &options)
+    };
+    // 0.587 and 0.7152 of white.
+    assert_eq!(
+        grey(Luma::Rec601),
+        "\x1B[38;2;150;150;150;48;2;150;150;150m▀\x1B[0m\n"
+    );
+    assert_eq!(
+        grey(Luma::Rec709),
+        "\x1B[38;2;182;182;182;48;2;182;182;182m▀\x1B[0m\n"
+    );
+}
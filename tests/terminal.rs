@@ -0,0 +1,33 @@
+//! Terminal properties read from environment variables.
+
+use ttview::terminal::size_from_env;
+
+/// Look up variables in `vars` instead of the environment.
+fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+    |name| {
+        vars.iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.to_string())
+    }
+}
+
+#[test]
+fn size_from_columns_and_lines() {
+    assert_eq!(size_from_env(env(&[("COLUMNS", "120")])), Some((120, None)));
+    assert_eq!(
+        size_from_env(env(&[("COLUMNS", " 120\n"), ("LINES", "40")])),
+        Some((120, Some(40)))
+    );
+}
+
+#[test]
+fn invalid_sizes_are_ignored() {
+    assert_eq!(size_from_env(env(&[])), None);
+    assert_eq!(size_from_env(env(&[("LINES", "40")])), None);
+    assert_eq!(size_from_env(env(&[("COLUMNS", "0")])), None);
+    assert_eq!(size_from_env(env(&[("COLUMNS", "wide")])), None);
+    assert_eq!(
+        size_from_env(env(&[("COLUMNS", "120"), ("LINES", "-1")])),
+        Some((120, None))
+    );
+}
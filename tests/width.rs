@@ -0,0 +1,28 @@
+//! Gradient characters that would not fit a single terminal column.
+
+use ttview::styling::{ASCII_GRADIENT, GradientPreset, non_single_width};
+
+#[test]
+fn presets_are_single_width() {
+    assert_eq!(non_single_width(ASCII_GRADIENT), None);
+    for preset in [
+        GradientPreset::Standard,
+        GradientPreset::Blocks,
+        GradientPreset::Minimal,
+        GradientPreset::Long,
+    ] {
+        assert_eq!(non_single_width(preset.characters()), None, "{preset:?}");
+    }
+}
+
+#[test]
+fn detects_wide_characters() {
+    assert_eq!(non_single_width(" .字@"), Some('字'));
+    assert_eq!(non_single_width(" 🙂"), Some('🙂'));
+}
+
+#[test]
+fn detects_zero_width_characters() {
+    assert_eq!(non_single_width(" .e\u{301}"), Some('\u{301}'));
+    assert_eq!(non_single_width(" \t#"), Some('\t'));
+}